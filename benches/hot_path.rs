@@ -0,0 +1,212 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! Compare the hot path of `expect!` against `.expect()` and the old inline `panic!` expansion.
+//!
+//! Each function below unwraps through 16 call sites with distinct locations, so every site keeps
+//! its own failure branch and the size of each expanded call site adds up. Run with `cargo bench`:
+//! it prints the time per call of each function for an `Option` and a `Result`, then their sizes
+//! as listed by `nm`, if it is installed. The sizes depend on the compiler, so they are reported
+//! rather than asserted.
+
+#[macro_use]
+extern crate expect_macro;
+
+use std::env;
+use std::hint::black_box;
+use std::process::Command;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 1_000_000;
+
+macro_rules! sum16 {
+    ($name:ident: $ty:ty, |$v:ident| $($unwrap:expr),+) => {
+        #[inline(never)]
+        fn $name($v: &[$ty; 16]) -> u64 {
+            0 $(+ $unwrap)+
+        }
+    };
+}
+
+macro_rules! inline_panic {
+    ($result:expr) => {
+        $result.unwrap_or_else(|e| panic!("{:?}", e))
+    };
+}
+
+sum16!(
+    sum_expect_macro: Option<u64>,
+    |v| expect!(v[0]),
+    expect!(v[1]),
+    expect!(v[2]),
+    expect!(v[3]),
+    expect!(v[4]),
+    expect!(v[5]),
+    expect!(v[6]),
+    expect!(v[7]),
+    expect!(v[8]),
+    expect!(v[9]),
+    expect!(v[10]),
+    expect!(v[11]),
+    expect!(v[12]),
+    expect!(v[13]),
+    expect!(v[14]),
+    expect!(v[15])
+);
+
+sum16!(
+    sum_expect_method: Option<u64>,
+    |v| v[0].expect("value"),
+    v[1].expect("value"),
+    v[2].expect("value"),
+    v[3].expect("value"),
+    v[4].expect("value"),
+    v[5].expect("value"),
+    v[6].expect("value"),
+    v[7].expect("value"),
+    v[8].expect("value"),
+    v[9].expect("value"),
+    v[10].expect("value"),
+    v[11].expect("value"),
+    v[12].expect("value"),
+    v[13].expect("value"),
+    v[14].expect("value"),
+    v[15].expect("value")
+);
+
+sum16!(
+    sum_inline_panic: Option<u64>,
+    |v| inline_panic!(v[0].ok_or("Got value of None")),
+    inline_panic!(v[1].ok_or("Got value of None")),
+    inline_panic!(v[2].ok_or("Got value of None")),
+    inline_panic!(v[3].ok_or("Got value of None")),
+    inline_panic!(v[4].ok_or("Got value of None")),
+    inline_panic!(v[5].ok_or("Got value of None")),
+    inline_panic!(v[6].ok_or("Got value of None")),
+    inline_panic!(v[7].ok_or("Got value of None")),
+    inline_panic!(v[8].ok_or("Got value of None")),
+    inline_panic!(v[9].ok_or("Got value of None")),
+    inline_panic!(v[10].ok_or("Got value of None")),
+    inline_panic!(v[11].ok_or("Got value of None")),
+    inline_panic!(v[12].ok_or("Got value of None")),
+    inline_panic!(v[13].ok_or("Got value of None")),
+    inline_panic!(v[14].ok_or("Got value of None")),
+    inline_panic!(v[15].ok_or("Got value of None"))
+);
+
+sum16!(
+    sum_expect_macro_result: Result<u64, &'static str>,
+    |v| expect!(v[0]),
+    expect!(v[1]),
+    expect!(v[2]),
+    expect!(v[3]),
+    expect!(v[4]),
+    expect!(v[5]),
+    expect!(v[6]),
+    expect!(v[7]),
+    expect!(v[8]),
+    expect!(v[9]),
+    expect!(v[10]),
+    expect!(v[11]),
+    expect!(v[12]),
+    expect!(v[13]),
+    expect!(v[14]),
+    expect!(v[15])
+);
+
+sum16!(
+    sum_expect_method_result: Result<u64, &'static str>,
+    |v| v[0].expect("value"),
+    v[1].expect("value"),
+    v[2].expect("value"),
+    v[3].expect("value"),
+    v[4].expect("value"),
+    v[5].expect("value"),
+    v[6].expect("value"),
+    v[7].expect("value"),
+    v[8].expect("value"),
+    v[9].expect("value"),
+    v[10].expect("value"),
+    v[11].expect("value"),
+    v[12].expect("value"),
+    v[13].expect("value"),
+    v[14].expect("value"),
+    v[15].expect("value")
+);
+
+sum16!(
+    sum_inline_panic_result: Result<u64, &'static str>,
+    |v| inline_panic!(v[0]),
+    inline_panic!(v[1]),
+    inline_panic!(v[2]),
+    inline_panic!(v[3]),
+    inline_panic!(v[4]),
+    inline_panic!(v[5]),
+    inline_panic!(v[6]),
+    inline_panic!(v[7]),
+    inline_panic!(v[8]),
+    inline_panic!(v[9]),
+    inline_panic!(v[10]),
+    inline_panic!(v[11]),
+    inline_panic!(v[12]),
+    inline_panic!(v[13]),
+    inline_panic!(v[14]),
+    inline_panic!(v[15])
+);
+
+fn bench<T: Copy>(name: &str, value: T, f: fn(&[T; 16]) -> u64) {
+    let values = [value; 16];
+    let start = Instant::now();
+    let mut total = 0;
+    for _ in 0..ITERATIONS {
+        total += f(black_box(&values));
+    }
+    let elapsed = start.elapsed();
+    assert_eq!(total, 16 * u64::from(ITERATIONS));
+    println!(
+        "{:<22} {:>6.2} ns/iter",
+        name,
+        elapsed.as_nanos() as f64 / f64::from(ITERATIONS),
+    );
+}
+
+/// Print the size of each `sum_` function in this binary, as listed by `nm`.
+fn print_sizes() {
+    let exe = env::current_exe().unwrap();
+    let output = match Command::new("nm").arg("--print-size").arg(&exe).output() {
+        Ok(output) if output.status.success() => output.stdout,
+        _ => return println!("code size: `nm` is not available, skipped"),
+    };
+    let symbols = String::from_utf8_lossy(&output);
+    let mut sizes: Vec<(&str, u64)> = symbols
+        .lines()
+        .filter_map(|line| {
+            // `address size type symbol`, where the mangled symbol contains the function name.
+            let mut fields = line.split_whitespace();
+            let size = u64::from_str_radix(fields.nth(1)?, 16).ok()?;
+            let symbol = fields.nth(1)?;
+            let start = symbol.find("sum_")?;
+            let name = &symbol[start..];
+            let end = name.find(|c: char| c != '_' && !c.is_ascii_lowercase())?;
+            Some((&name[..end], size))
+        })
+        .collect();
+    sizes.sort();
+    for (name, size) in sizes {
+        println!("{:<24} {:>6} bytes", name, size);
+    }
+}
+
+fn main() {
+    bench("expect!", Some(1), sum_expect_macro);
+    bench(".expect()", Some(1), sum_expect_method);
+    bench("inline panic!", Some(1), sum_inline_panic);
+    bench("expect! (Result)", Ok(1), sum_expect_macro_result);
+    bench(".expect() (Result)", Ok(1), sum_expect_method_result);
+    bench("inline panic! (Result)", Ok(1), sum_inline_panic_result);
+    print_sizes();
+}
@@ -0,0 +1,318 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! `#[derive(IntoResult)]` for the `expect_macro` crate.
+//!
+//! Use it through the `derive` feature of `expect_macro` rather than directly, see the docs of
+//! `expect_macro::IntoResult`.
+//!
+//! This parses the enum by hand instead of depending on `syn`: an enum definition is simple
+//! enough, and it keeps the feature free of dependencies.
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+use std::iter::{FromIterator, Peekable};
+
+/// Implement `expect_macro::IntoResult` for an enum.
+///
+/// The variant marked `#[into_result(ok)]` is the success: its fields are the `Ok` value (`()`
+/// for a unit variant, a tuple for several fields). Every other variant is an error, returned as
+/// an `expect_macro::VariantError` naming the variant.
+#[proc_macro_derive(IntoResult, attributes(into_result))]
+pub fn derive_into_result(input: TokenStream) -> TokenStream {
+    let code = match expand(input) {
+        Ok(code) => code,
+        Err(msg) => format!("compile_error!({:?});", msg),
+    };
+    code.parse().expect("generated invalid tokens")
+}
+
+struct Variant {
+    name: String,
+    ok: bool,
+    fields: Fields,
+}
+
+enum Fields {
+    Unit,
+    Tuple(Vec<String>),
+    Named,
+}
+
+fn expand(input: TokenStream) -> Result<String, String> {
+    let mut tokens = input.into_iter().peekable();
+    skip_attributes_and_visibility(&mut tokens);
+
+    match tokens.next() {
+        Some(TokenTree::Ident(ref ident)) if ident.to_string() == "enum" => {}
+        _ => return Err("#[derive(IntoResult)] only supports enums".into()),
+    }
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("#[derive(IntoResult)] expected the name of the enum".into()),
+    };
+
+    let mut impl_generics = Vec::new();
+    let mut type_generics = Vec::new();
+    if is_punct(tokens.peek(), '<') {
+        tokens.next();
+        for param in split_top_level(take_generics(&mut tokens)) {
+            let (impl_param, type_param) = generic_param(param);
+            impl_generics.push(impl_param);
+            type_generics.push(type_param);
+        }
+    }
+
+    let mut where_clause = Vec::new();
+    let body = loop {
+        match tokens.next() {
+            Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Brace => {
+                break group.stream();
+            }
+            Some(token) => where_clause.push(token),
+            None => return Err("#[derive(IntoResult)] expected the body of the enum".into()),
+        }
+    };
+
+    let variants = split_top_level(body.into_iter().collect())
+        .into_iter()
+        .map(variant)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut oks = variants.iter().filter(|v| v.ok);
+    let ok = match (oks.next(), oks.next()) {
+        (Some(ok), None) => ok,
+        _ => {
+            return Err(
+                "#[derive(IntoResult)] needs exactly one variant marked #[into_result(ok)]".into(),
+            )
+        }
+    };
+    let (ok_type, ok_pattern, ok_value) = match ok.fields {
+        Fields::Unit => ("()".to_string(), String::new(), "()".to_string()),
+        Fields::Tuple(ref types) => {
+            let bindings = (0..types.len())
+                .map(|i| format!("f{}", i))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let pattern = format!("({})", bindings);
+            if types.len() == 1 {
+                (types[0].clone(), pattern, bindings)
+            } else {
+                (
+                    format!("({},)", types.join(", ")),
+                    pattern,
+                    format!("({},)", bindings),
+                )
+            }
+        }
+        Fields::Named => {
+            return Err("#[into_result(ok)] can only be used on a unit or tuple variant".into())
+        }
+    };
+
+    let self_type = if type_generics.is_empty() {
+        name.clone()
+    } else {
+        format!("{}<{}>", name, type_generics.join(", "))
+    };
+    let error_type = format!("::expect_macro::VariantError<{}>", self_type);
+    let found_arms = variants
+        .iter()
+        .map(|v| format!("{0}::{1} {{ .. }} => \"{0}::{1}\",", name, v.name))
+        .collect::<String>();
+
+    Ok(format!(
+        "impl<{impl_generics}> ::expect_macro::IntoResult<{ok_type}, {error_type}> \
+         for {self_type} {where_clause} {{
+            #[allow(unreachable_patterns)]
+            fn into_result(self) -> ::std::result::Result<{ok_type}, {error_type}> {{
+                match self {{
+                    {name}::{ok_name}{ok_pattern} => ::std::result::Result::Ok({ok_value}),
+                    other => {{
+                        let found = match other {{ {found_arms} }};
+                        ::std::result::Result::Err(
+                            ::expect_macro::VariantError::new(\"{name}::{ok_name}\", found, other)
+                        )
+                    }}
+                }}
+            }}
+
+            fn __error_ref<'e>(error: &'e {error_type}) -> ::expect_macro::__private::ErrorRef<'e> {{
+                ::expect_macro::__private::ErrorRef::Error(error)
+            }}
+        }}",
+        impl_generics = impl_generics.join(", "),
+        ok_type = ok_type,
+        error_type = error_type,
+        self_type = self_type,
+        where_clause = TokenStream::from_iter(where_clause),
+        name = name,
+        ok_name = ok.name,
+        ok_pattern = ok_pattern,
+        ok_value = ok_value,
+        found_arms = found_arms,
+    ))
+}
+
+/// Parse a variant, i.e. `#[into_result(ok)] Found(Row)`.
+fn variant(tokens: Vec<TokenTree>) -> Result<Variant, String> {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut ok = false;
+    while is_punct(tokens.peek(), '#') {
+        tokens.next();
+        if let Some(TokenTree::Group(attr)) = tokens.next() {
+            ok |= is_ok_attribute(attr.stream());
+        }
+    }
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("#[derive(IntoResult)] expected the name of a variant".into()),
+    };
+    let fields = match tokens.next() {
+        Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Parenthesis => {
+            let types = split_top_level(group.stream().into_iter().collect())
+                .into_iter()
+                .map(|field| {
+                    let mut field = field.into_iter().peekable();
+                    skip_attributes_and_visibility(&mut field);
+                    TokenStream::from_iter(field).to_string()
+                })
+                .collect();
+            Fields::Tuple(types)
+        }
+        Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Brace => Fields::Named,
+        _ => Fields::Unit,
+    };
+    Ok(Variant { name, ok, fields })
+}
+
+/// Whether the contents of `#[...]` are `into_result(ok)`.
+fn is_ok_attribute(attr: TokenStream) -> bool {
+    let mut tokens = attr.into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(ref ident)), Some(TokenTree::Group(ref args)))
+            if ident.to_string() == "into_result" =>
+        {
+            args.stream().to_string().trim() == "ok"
+        }
+        _ => false,
+    }
+}
+
+/// Split a generic parameter into its form in `impl<...>` (without a default) and in the type.
+fn generic_param(param: Vec<TokenTree>) -> (String, String) {
+    let without_default = match param.iter().position(|t| is_punct(Some(t), '=')) {
+        Some(i) => param[..i].to_vec(),
+        None => param.clone(),
+    };
+    let type_param = match (param.first(), param.get(1)) {
+        // A lifetime is a joint `'` and its name.
+        (Some(TokenTree::Punct(p)), Some(name)) if p.as_char() == '\'' => {
+            format!("'{}", name)
+        }
+        (Some(TokenTree::Ident(ident)), Some(name)) if ident.to_string() == "const" => {
+            name.to_string()
+        }
+        (Some(name), _) => name.to_string(),
+        (None, _) => String::new(),
+    };
+    (
+        TokenStream::from_iter(without_default).to_string(),
+        type_param,
+    )
+}
+
+/// Take the tokens up to the `>` closing generics whose `<` was already consumed.
+fn take_generics<I: Iterator<Item = TokenTree>>(tokens: &mut Peekable<I>) -> Vec<TokenTree> {
+    let mut depth = 1;
+    let mut generics = Vec::new();
+    let mut after_dash = false;
+    for token in tokens {
+        match token {
+            TokenTree::Punct(ref p) if p.as_char() == '<' => depth += 1,
+            // Skip the `>` of `->` in bounds like `F: Fn() -> T`.
+            TokenTree::Punct(ref p) if p.as_char() == '>' && !after_dash => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        after_dash = match token {
+            TokenTree::Punct(ref p) => p.as_char() == '-' && p.spacing() == Spacing::Joint,
+            _ => false,
+        };
+        generics.push(token);
+    }
+    generics
+}
+
+/// Split on commas that are not nested inside `<...>` (groups are already single tokens).
+fn split_top_level(tokens: Vec<TokenTree>) -> Vec<Vec<TokenTree>> {
+    let mut parts = Vec::new();
+    let mut current = Vec::new();
+    let mut depth = 0;
+    let mut after_dash = false;
+    for token in tokens {
+        let is_dash = match token {
+            TokenTree::Punct(ref p) => match p.as_char() {
+                '<' => {
+                    depth += 1;
+                    false
+                }
+                '>' if !after_dash => {
+                    depth -= 1;
+                    false
+                }
+                ',' if depth == 0 => {
+                    parts.push(current);
+                    current = Vec::new();
+                    after_dash = false;
+                    continue;
+                }
+                c => c == '-' && p.spacing() == Spacing::Joint,
+            },
+            _ => false,
+        };
+        after_dash = is_dash;
+        current.push(token);
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+fn skip_attributes_and_visibility<I: Iterator<Item = TokenTree>>(tokens: &mut Peekable<I>) {
+    loop {
+        if is_punct(tokens.peek(), '#') {
+            tokens.next();
+            tokens.next();
+            continue;
+        }
+        match tokens.peek() {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "pub" => {}
+            _ => return,
+        }
+        tokens.next();
+        if let Some(TokenTree::Group(group)) = tokens.peek() {
+            if group.delimiter() == Delimiter::Parenthesis {
+                tokens.next();
+            }
+        }
+    }
+}
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    match token {
+        Some(TokenTree::Punct(p)) => p.as_char() == c,
+        _ => false,
+    }
+}
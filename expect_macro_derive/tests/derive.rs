@@ -0,0 +1,76 @@
+#[macro_use]
+extern crate expect_macro;
+
+use expect_macro::{ExpectFailure, IntoResult, VariantError};
+use std::panic;
+
+#[derive(Debug, PartialEq)]
+struct Row(u32);
+
+#[derive(Debug, PartialEq, IntoResult)]
+enum Lookup {
+    #[into_result(ok)]
+    Found(Row),
+    Missing(&'static str),
+    Denied {
+        reason: String,
+    },
+}
+
+#[derive(IntoResult)]
+pub enum Pair<'a, T: Clone + 'a, F = u8>
+where
+    T: PartialEq,
+{
+    Neither,
+    #[into_result(ok)]
+    Both(&'a T, Vec<Option<T>>),
+    Other(F),
+}
+
+#[derive(IntoResult)]
+enum Flag {
+    #[into_result(ok)]
+    Set,
+    Unset,
+}
+
+#[test]
+fn derive_ok_variant() {
+    assert_eq!(expect!(Lookup::Found(Row(1))), Row(1));
+    assert_eq!(expect!(Pair::<u32>::Both(&1, vec![None])), (&1, vec![None]));
+    let () = expect!(Flag::Set);
+}
+
+#[test]
+fn derive_error_variants() {
+    let err: VariantError<Lookup> = Lookup::Missing("key").into_result().unwrap_err();
+    assert_eq!(err.expected(), "Lookup::Found");
+    assert_eq!(err.found(), "Lookup::Missing");
+    assert_eq!(err.into_inner(), Lookup::Missing("key"));
+
+    let err = Lookup::Denied {
+        reason: "no".into(),
+    }
+    .into_result()
+    .unwrap_err();
+    assert_eq!(err.found(), "Lookup::Denied");
+
+    let err = Pair::<u32, i8>::Other(-1).into_result().unwrap_err();
+    assert_eq!(err.to_string(), "expected Pair::Both, got Pair::Other");
+    assert!(Flag::Unset.into_result().is_err());
+}
+
+#[test]
+fn derive_expect_message() {
+    let payload = panic::catch_unwind(|| {
+        expect!(Lookup::Missing("key"));
+    })
+    .unwrap_err();
+
+    let failure = payload.downcast_ref::<ExpectFailure>().unwrap();
+    assert_eq!(
+        failure.to_string(),
+        "`expect!(Lookup::Missing(\"key\"))` failed: expected Lookup::Found, got Lookup::Missing"
+    );
+}
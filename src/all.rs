@@ -0,0 +1,92 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! Support for the macros that unwrap several operands at once.
+
+use std::fmt;
+
+use format;
+use render::ErrorRef;
+
+/// Render the error of one operand now, since it is dropped before the failure is reported.
+#[doc(hidden)]
+#[cold]
+#[inline(never)]
+pub fn render_operand(error: ErrorRef) -> String {
+    error.render(format::format()).to_string()
+}
+
+/// The stringified expression and rendered error of every operand that failed.
+#[doc(hidden)]
+pub struct Failures<'a>(pub &'a [(&'static str, String)]);
+
+impl<'a> fmt::Display for Failures<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.len() {
+            1 => f.write_str("1 operand failed")?,
+            n => write!(f, "{} operands failed", n)?,
+        }
+        for &(expr, ref error) in self.0 {
+            write!(f, "\n  - `{}`: {}", expr, error.replace('\n', "\n    "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The stringified expression and rendered error of every attempt, in order.
+#[doc(hidden)]
+pub struct Attempts<'a>(pub &'a [(&'static str, String)]);
+
+impl<'a> fmt::Display for Attempts<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("every attempt failed")?;
+        for (i, &(expr, ref error)) in self.0.iter().enumerate() {
+            write!(
+                f,
+                "\n  {}. `{}`: {}",
+                i + 1,
+                expr,
+                error.replace('\n', "\n     ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Unwrap a tuple of `Option`s, all of which are known to be `Some`.
+#[doc(hidden)]
+pub trait UnwrapAll {
+    type Output;
+    fn unwrap_all(self) -> Self::Output;
+}
+
+macro_rules! unwrap_all {
+    ($($T:ident),+) => {
+        impl<$($T),+> UnwrapAll for ($(Option<$T>,)+) {
+            type Output = ($($T,)+);
+
+            #[allow(non_snake_case)]
+            fn unwrap_all(self) -> Self::Output {
+                let ($($T,)+) = self;
+                ($($T.unwrap(),)+)
+            }
+        }
+    };
+}
+
+unwrap_all!(A);
+unwrap_all!(A, B);
+unwrap_all!(A, B, C);
+unwrap_all!(A, B, C, D);
+unwrap_all!(A, B, C, D, E);
+unwrap_all!(A, B, C, D, E, F);
+unwrap_all!(A, B, C, D, E, F, G);
+unwrap_all!(A, B, C, D, E, F, G, H);
+unwrap_all!(A, B, C, D, E, F, G, H, I);
+unwrap_all!(A, B, C, D, E, F, G, H, I, J);
+unwrap_all!(A, B, C, D, E, F, G, H, I, J, K);
+unwrap_all!(A, B, C, D, E, F, G, H, I, J, K, L);
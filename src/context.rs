@@ -0,0 +1,109 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! The thread local stack of `expect_context!` frames.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+
+/// A lazily formatted context frame, which may borrow for `'a`.
+type Frame<'a> = dyn Fn(&mut fmt::Formatter) -> fmt::Result + 'a;
+
+/// The frames of one thread, each with the id of the guard that owns it.
+struct Frames {
+    next_id: u64,
+    frames: Vec<(u64, Box<Frame<'static>>)>,
+}
+
+thread_local! {
+    static FRAMES: RefCell<Frames> = const {
+        RefCell::new(Frames {
+            next_id: 0,
+            frames: Vec::new(),
+        })
+    };
+}
+
+/// An [`expect_context!`] frame, which is removed from the thread's context stack when dropped.
+///
+/// `expect_context!` keeps it in a temporary of the enclosing block and returns a reference to
+/// it, so the frame can borrow the arguments of the macro for as long as the block runs.
+///
+/// Guards are usually dropped in the reverse order they were created, but not always: a guard
+/// held across an `.await` is dropped whenever its future is. So each guard removes the frame it
+/// pushed rather than the top one.
+///
+/// [`expect_context!`]: macro.expect_context.html
+#[must_use = "the frame is removed as soon as the guard is dropped"]
+pub struct ContextGuard<'a> {
+    id: u64,
+    // The frame borrows for `'a`, and frames live on one thread's stack.
+    _frame: PhantomData<(&'a (), *const ())>,
+}
+
+impl<'a> ContextGuard<'a> {
+    /// Push `frame` onto this thread's context stack.
+    ///
+    /// The frame is stored without its lifetime, so the guard must be dropped before `'a` ends
+    /// rather than leaked. `expect_context!` ensures that by never handing out the guard itself.
+    #[doc(hidden)]
+    pub fn push<F>(frame: F) -> ContextGuard<'a>
+    where
+        F: Fn(&mut fmt::Formatter) -> fmt::Result + 'a,
+    {
+        let frame: Box<Frame<'a>> = Box::new(frame);
+        // The guard removes the frame before `'a` ends, see above.
+        let frame = unsafe { mem::transmute::<Box<Frame<'a>>, Box<Frame<'static>>>(frame) };
+        let id = FRAMES.with(|frames| {
+            let mut frames = frames.borrow_mut();
+            let id = frames.next_id;
+            frames.next_id += 1;
+            frames.frames.push((id, frame));
+            id
+        });
+        ContextGuard {
+            id,
+            _frame: PhantomData,
+        }
+    }
+}
+
+impl<'a> Drop for ContextGuard<'a> {
+    fn drop(&mut self) {
+        let frame = FRAMES.try_with(|frames| {
+            let mut frames = frames.borrow_mut();
+            let index = frames.frames.iter().rposition(|&(id, _)| id == self.id)?;
+            Some(frames.frames.remove(index))
+        });
+        // Drop the frame's captures after the stack is released, in case they use it.
+        drop(frame);
+    }
+}
+
+struct Render<'a>(&'a Frame<'static>);
+
+impl<'a> fmt::Display for Render<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.0)(f)
+    }
+}
+
+/// Format the active frames, outermost first.
+pub(crate) fn render() -> Vec<String> {
+    FRAMES
+        .try_with(|frames| {
+            frames
+                .borrow()
+                .frames
+                .iter()
+                .map(|(_, frame)| Render(&**frame).to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,167 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! A line-oriented diff for the `{:#?}` renders of mismatched values.
+
+use std::cmp;
+use std::fmt;
+
+/// Lines of unchanged context printed around each change.
+const CONTEXT: usize = 3;
+
+/// The largest table of lines the longest common subsequence is computed for. Beyond that every
+/// differing line is reported as removed and then added, which is still correct, only longer.
+const MAX_TABLE: usize = 1 << 22;
+
+/// A unified diff of two multi-line strings, i.e. the `{:#?}` of two values.
+///
+/// The `Display` form starts with `--- left` and `+++ right` headers, followed by one `@@` hunk
+/// per group of changes with up to three lines of context around them. Lines only in `left` are
+/// prefixed with `-`, lines only in `right` with `+`.
+///
+/// `expect_eq!` and `expect_cmp!(a, ==, b)` use it for values whose `{:#?}` spans several lines,
+/// unless it is disabled with [`set_diff`]. Use it in your own comparison macros and handlers:
+///
+/// ```rust
+/// use expect_macro::LineDiff;
+///
+/// let diff = LineDiff::new("a\nb\nc", "a\nB\nc").to_string();
+/// assert_eq!(diff, "--- left\n+++ right\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c");
+/// ```
+///
+/// [`set_diff`]: fn.set_diff.html
+#[derive(Debug, Clone, Copy)]
+pub struct LineDiff<'a> {
+    left: &'a str,
+    right: &'a str,
+}
+
+impl<'a> LineDiff<'a> {
+    /// Diff `left` against `right`.
+    pub fn new(left: &'a str, right: &'a str) -> LineDiff<'a> {
+        LineDiff { left, right }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    Same,
+    Removed,
+    Added,
+}
+
+impl<'a> fmt::Display for LineDiff<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let left = self.left.lines().collect::<Vec<_>>();
+        let right = self.right.lines().collect::<Vec<_>>();
+        let lines = diff(&left, &right);
+
+        f.write_str("--- left\n+++ right")?;
+        for hunk in hunks(&lines) {
+            let (mut l, mut r) = (0, 0);
+            for &(tag, _) in &lines[..hunk.start] {
+                l += (tag != Tag::Added) as usize;
+                r += (tag != Tag::Removed) as usize;
+            }
+            let body = &lines[hunk];
+            let l_len = body.iter().filter(|&&(t, _)| t != Tag::Added).count();
+            let r_len = body.iter().filter(|&&(t, _)| t != Tag::Removed).count();
+            write!(
+                f,
+                "\n@@ -{},{} +{},{} @@",
+                l + (l_len != 0) as usize,
+                l_len,
+                r + (r_len != 0) as usize,
+                r_len
+            )?;
+            for &(tag, line) in body {
+                let prefix = match tag {
+                    Tag::Same => ' ',
+                    Tag::Removed => '-',
+                    Tag::Added => '+',
+                };
+                write!(f, "\n{}{}", prefix, line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The lines of both sides in order, tagged with which side they are in.
+fn diff<'s>(left: &[&'s str], right: &[&'s str]) -> Vec<(Tag, &'s str)> {
+    let prefix = left.iter().zip(right).take_while(|&(l, r)| l == r).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|&(l, r)| l == r)
+        .count();
+    let l_mid = &left[prefix..left.len() - suffix];
+    let r_mid = &right[prefix..right.len() - suffix];
+
+    let mut lines = Vec::with_capacity(left.len() + right.len());
+    lines.extend(left[..prefix].iter().map(|&l| (Tag::Same, l)));
+    if (l_mid.len() + 1).saturating_mul(r_mid.len() + 1) <= MAX_TABLE {
+        lcs(l_mid, r_mid, &mut lines);
+    } else {
+        lines.extend(l_mid.iter().map(|&l| (Tag::Removed, l)));
+        lines.extend(r_mid.iter().map(|&r| (Tag::Added, r)));
+    }
+    lines.extend(left[left.len() - suffix..].iter().map(|&l| (Tag::Same, l)));
+    lines
+}
+
+/// Diff using the longest common subsequence, removals before additions.
+fn lcs<'s>(left: &[&'s str], right: &[&'s str], lines: &mut Vec<(Tag, &'s str)>) {
+    let width = right.len() + 1;
+    // `table[i * width + j]` is the length of the LCS of `left[i..]` and `right[j..]`.
+    let mut table = vec![0u32; (left.len() + 1) * width];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            table[i * width + j] = if left[i] == right[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                cmp::max(table[(i + 1) * width + j], table[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i] == right[j] {
+            lines.push((Tag::Same, left[i]));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            lines.push((Tag::Removed, left[i]));
+            i += 1;
+        } else {
+            lines.push((Tag::Added, right[j]));
+            j += 1;
+        }
+    }
+    lines.extend(left[i..].iter().map(|&l| (Tag::Removed, l)));
+    lines.extend(right[j..].iter().map(|&r| (Tag::Added, r)));
+}
+
+/// The ranges of `lines` to print: every change plus `CONTEXT` lines around it, merging ranges
+/// that touch.
+fn hunks(lines: &[(Tag, &str)]) -> Vec<::std::ops::Range<usize>> {
+    let mut hunks: Vec<::std::ops::Range<usize>> = Vec::new();
+    for (i, &(tag, _)) in lines.iter().enumerate() {
+        if tag == Tag::Same {
+            continue;
+        }
+        let start = i.saturating_sub(CONTEXT);
+        let end = cmp::min(i + 1 + CONTEXT, lines.len());
+        match hunks.last_mut() {
+            Some(last) if last.end >= start => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+    hunks
+}
@@ -0,0 +1,268 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! Error types for the `IntoResult` implementors that have no error of their own.
+
+use std::any;
+use std::error::Error;
+use std::fmt;
+use std::panic::Location;
+
+use failure::{fail, Site};
+use render::ErrorRef;
+
+/// The error of an `Option<T>` that was `None`.
+///
+/// ```rust
+/// use expect_macro::{IntoResult, NoneError};
+///
+/// let err: NoneError = None::<String>.into_result().unwrap_err();
+/// assert_eq!(err.to_string(), "expected Some(alloc::string::String), got None");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct NoneError {
+    type_name: &'static str,
+    location: Option<&'static Location<'static>>,
+}
+
+impl NoneError {
+    /// Create the error for a `None` of type `Option<T>`.
+    pub fn new<T>() -> NoneError {
+        NoneError {
+            type_name: any::type_name::<T>(),
+            location: None,
+        }
+    }
+
+    /// The `std::any::type_name` of `T` in `Option<T>`.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Where `into_result` was called, which for the macros is their call site.
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        self.location
+    }
+}
+
+impl fmt::Display for NoneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected Some({}), got None", self.type_name)
+    }
+}
+
+impl fmt::Debug for NoneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Error for NoneError {}
+
+/// The error of a `bool` that was `false`.
+///
+/// Its `Debug` includes the stringified condition when the macros supply it, i.e. ``condition was
+/// false: `path.exists()` ``. This lets `expect!` double as an invariant check whose failures
+/// read like the others:
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+/// use std::path::Path;
+///
+/// # fn main() {
+/// let path = Path::new("/does/not/exist");
+/// expect!(path.exists(), "missing {}", path.display());
+/// # }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct FalseError {
+    expr: Option<&'static str>,
+    location: Option<&'static Location<'static>>,
+}
+
+impl FalseError {
+    /// Create the error for a `false` condition.
+    pub fn new() -> FalseError {
+        FalseError {
+            expr: None,
+            location: None,
+        }
+    }
+
+    /// The stringified condition, which for the macros is their argument.
+    pub fn expr(&self) -> Option<&'static str> {
+        self.expr
+    }
+
+    /// Where `into_result` was called, which for the macros is their call site.
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        self.location
+    }
+}
+
+impl Default for FalseError {
+    fn default() -> FalseError {
+        FalseError::new()
+    }
+}
+
+impl fmt::Display for FalseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("condition was false")
+    }
+}
+
+impl fmt::Debug for FalseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.expr {
+            Some(expr) => write!(f, "condition was false: `{}`", expr),
+            None => fmt::Display::fmt(self, f),
+        }
+    }
+}
+
+impl Error for FalseError {}
+
+/// The error of a nested wrapper such as `Option<Result<T, E>>`, saying which layer failed.
+///
+/// Returned by [`flatten`], which is what `expect_flat!` uses.
+///
+/// [`flatten`]: fn.flatten.html
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FlatError<O, I> {
+    /// The outer wrapper failed, i.e. the `None` of `Option<Result<T, E>>`.
+    Outer(O),
+    /// The inner wrapper failed, i.e. the `Err` of `Option<Result<T, E>>`.
+    Inner(I),
+}
+
+impl<O: fmt::Debug, I: fmt::Debug> fmt::Debug for FlatError<O, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FlatError::Outer(ref e) => write!(f, "outer layer failed: {:?}", e),
+            FlatError::Inner(ref e) => write!(f, "inner layer failed: {:?}", e),
+        }
+    }
+}
+
+impl<O: fmt::Display, I: fmt::Display> fmt::Display for FlatError<O, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FlatError::Outer(ref e) => write!(f, "outer layer failed: {}", e),
+            FlatError::Inner(ref e) => write!(f, "inner layer failed: {}", e),
+        }
+    }
+}
+
+impl<O: Error, I: Error> Error for FlatError<O, I> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            FlatError::Outer(ref e) => e.source(),
+            FlatError::Inner(ref e) => e.source(),
+        }
+    }
+}
+
+/// The error of an enum that was not the expected variant.
+///
+/// This is the error of `#[derive(IntoResult)]` enums. It keeps the whole value, and its
+/// `Debug`/`Display` only name the variants, so it is readable even when the enum is not `Debug`.
+///
+/// ```rust
+/// use expect_macro::VariantError;
+///
+/// let err = VariantError::new("Lookup::Found", "Lookup::Missing", 42);
+/// assert_eq!(err.to_string(), "expected Lookup::Found, got Lookup::Missing");
+/// assert_eq!(err.into_inner(), 42);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct VariantError<E> {
+    expected: &'static str,
+    found: &'static str,
+    value: E,
+}
+
+impl<E> VariantError<E> {
+    /// Create the error for `value`, which is the variant `found` instead of `expected`.
+    pub fn new(expected: &'static str, found: &'static str, value: E) -> VariantError<E> {
+        VariantError {
+            expected,
+            found,
+            value,
+        }
+    }
+
+    /// The path of the expected variant, i.e. `"Lookup::Found"`.
+    pub fn expected(&self) -> &'static str {
+        self.expected
+    }
+
+    /// The path of the variant that was hit, i.e. `"Lookup::Missing"`.
+    pub fn found(&self) -> &'static str {
+        self.found
+    }
+
+    /// The value that was not the expected variant.
+    pub fn value(&self) -> &E {
+        &self.value
+    }
+
+    /// Take the value that was not the expected variant.
+    pub fn into_inner(self) -> E {
+        self.value
+    }
+}
+
+impl<E> fmt::Display for VariantError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {}, got {}", self.expected, self.found)
+    }
+}
+
+impl<E> fmt::Debug for VariantError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<E> Error for VariantError<E> {}
+
+/// Create a `NoneError` carrying the caller's location.
+#[cold]
+#[track_caller]
+pub(crate) fn none_error<T>() -> NoneError {
+    NoneError {
+        location: Some(Location::caller()),
+        ..NoneError::new::<T>()
+    }
+}
+
+/// Fail with the `NoneError` of an `Option<T>`, building it off the hot path.
+#[cold]
+#[inline(never)]
+#[track_caller]
+pub(crate) fn fail_none<T>(site: &'static Site) -> ! {
+    fail(site, ErrorRef::Error(&none_error::<T>()), None)
+}
+
+/// Create a `FalseError` for `expr` carrying the caller's location.
+#[cold]
+#[track_caller]
+pub(crate) fn false_error(expr: Option<&'static str>) -> FalseError {
+    FalseError {
+        expr,
+        location: Some(Location::caller()),
+    }
+}
+
+/// Fail with the `FalseError` of a `bool`, building it off the hot path.
+#[cold]
+#[inline(never)]
+#[track_caller]
+pub(crate) fn fail_false(site: &'static Site) -> ! {
+    fail(site, ErrorRef::Error(&false_error(Some(site.expr))), None)
+}
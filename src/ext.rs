@@ -0,0 +1,107 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! Method-style alternatives to the macros.
+
+use std::fmt;
+
+use failure::{fail, Site};
+use IntoResult;
+
+static EXPECT_HERE: Site = Site {
+    module_path: "",
+    macro_name: "expect_here",
+    expr: "",
+    format: None,
+};
+
+static EXPECT_WITH: Site = Site {
+    module_path: "",
+    macro_name: "expect_with",
+    expr: "",
+    format: None,
+};
+
+static EXPECT_MSG: Site = Site {
+    module_path: "",
+    macro_name: "expect_msg",
+    expr: "",
+    format: None,
+};
+
+/// Method versions of `expect!`, for long iterator or builder chains.
+///
+/// Implemented for every [`IntoResult`] type. The methods are `#[track_caller]`, so a failure is
+/// reported at the line calling them just like `expect!`. They cannot see the source of the
+/// expression, so [`ExpectFailure::expr`] and [`ExpectFailure::module_path`] are empty.
+///
+/// The error is rendered like `expect!` renders it for the `Option`, `bool`, `Poll`,
+/// `ControlFlow` and derived implementors, whose error types are known. The error of a
+/// `Result<T, E>` is rendered with `Debug`: unlike the macros, a generic method cannot tell
+/// whether `E` also implements `Error`, so use `expect!` to get its `Caused by:` chain.
+///
+/// [`IntoResult`]: trait.IntoResult.html
+/// [`ExpectFailure::expr`]: struct.ExpectFailure.html#method.expr
+/// [`ExpectFailure::module_path`]: struct.ExpectFailure.html#method.module_path
+///
+/// # Example
+///
+/// ```rust
+/// use expect_macro::ExpectExt;
+///
+/// let total: u32 = ["1", "2", "3"]
+///     .iter()
+///     .map(|s| s.parse::<u32>().expect_with(|e| format!("bad number {:?}: {}", s, e)))
+///     .sum();
+/// assert_eq!(total, 6);
+/// ```
+pub trait ExpectExt<T, E>: IntoResult<T, E> + Sized {
+    /// Unwrap or panic with the `Debug` of the error.
+    #[track_caller]
+    fn expect_here(self) -> T
+    where
+        E: fmt::Debug,
+    {
+        match self.into_result() {
+            Ok(v) => v,
+            Err(e) => fail(&EXPECT_HERE, Self::__error_ref(&e), None),
+        }
+    }
+
+    /// Unwrap or panic with the message built by `f`, which is only called on failure.
+    #[track_caller]
+    fn expect_with<F: FnOnce(&E) -> String>(self, f: F) -> T
+    where
+        E: fmt::Debug,
+    {
+        match self.into_result() {
+            Ok(v) => v,
+            Err(e) => {
+                let message = f(&e);
+                fail(
+                    &EXPECT_WITH,
+                    Self::__error_ref(&e),
+                    Some(format_args!("{}", message)),
+                )
+            }
+        }
+    }
+
+    /// Unwrap or panic with `args`, i.e. `.expect_msg(format_args!("need {}", 42))`.
+    #[track_caller]
+    fn expect_msg(self, args: fmt::Arguments) -> T
+    where
+        E: fmt::Debug,
+    {
+        match self.into_result() {
+            Ok(v) => v,
+            Err(e) => fail(&EXPECT_MSG, Self::__error_ref(&e), Some(args)),
+        }
+    }
+}
+
+impl<T, E, R: IntoResult<T, E>> ExpectExt<T, E> for R {}
@@ -0,0 +1,313 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! The structured payload `expect!` panics with.
+
+use std::backtrace::Backtrace;
+use std::env;
+use std::fmt;
+use std::panic::{self, Location};
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "backtrace")]
+use std::sync::Arc;
+use std::sync::Once;
+use std::thread;
+
+use context;
+use format::{self, Format};
+use handler;
+use render::ErrorRef;
+
+/// The panic payload of a failed `expect!`.
+///
+/// Recover it with [`std::panic::catch_unwind`] and `downcast_ref`:
+///
+/// ```rust
+/// #[macro_use] extern crate expect_macro;
+/// use expect_macro::ExpectFailure;
+/// use std::panic;
+///
+/// # fn main() {
+/// let payload = panic::catch_unwind(|| {
+///     let result: Result<u32, &str> = Err("expect error");
+///     expect!(result, "needed a value");
+/// }).unwrap_err();
+///
+/// let failure = payload.downcast_ref::<ExpectFailure>().unwrap();
+/// assert_eq!(failure.expr(), "result");
+/// assert_eq!(failure.error(), "\"expect error\"");
+/// assert_eq!(failure.message(), Some("needed a value"));
+/// # }
+/// ```
+///
+/// The `Display` form is the custom message if one was given, otherwise the stringified expression
+/// followed by the error, i.e.
+/// ``"`expect!(config.get("port"))` failed: expected Some(..), got None"``.
+/// Any [`context`](#method.context) follows on its own lines. When `expect_eq!` fails on values
+/// whose `{:#?}` spans several lines the error is a [`LineDiff`] of them, see [`set_diff`].
+///
+/// [`std::panic::catch_unwind`]: https://doc.rust-lang.org/std/panic/fn.catch_unwind.html
+/// [`LineDiff`]: struct.LineDiff.html
+/// [`set_diff`]: fn.set_diff.html
+#[derive(Debug, Clone)]
+pub struct ExpectFailure {
+    file: &'static str,
+    line: u32,
+    column: u32,
+    module_path: &'static str,
+    macro_name: &'static str,
+    expr: &'static str,
+    error: String,
+    message: Option<String>,
+    context: Vec<String>,
+    thread: Option<String>,
+    #[cfg(feature = "backtrace")]
+    backtrace: Arc<Backtrace>,
+}
+
+impl ExpectFailure {
+    /// The file of the failed `expect!`.
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// The line of the failed `expect!`.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The column of the failed `expect!`.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    /// The `module_path!()` of the failed `expect!`, empty for the `ExpectExt` methods.
+    pub fn module_path(&self) -> &'static str {
+        self.module_path
+    }
+
+    /// The name of the macro (or `ExpectExt` method) that failed, i.e. `"expect"` or
+    /// `"expect_err"`.
+    pub fn macro_name(&self) -> &'static str {
+        self.macro_name
+    }
+
+    /// The stringified expression that was unwrapped, empty for the `ExpectExt` methods.
+    pub fn expr(&self) -> &'static str {
+        self.expr
+    }
+
+    /// The rendered `Err`/`None` value (or the unexpected success for `expect_err!` and
+    /// `expect_none!`). This is the `Display` and `source()` chain of a `std::error::Error`,
+    /// otherwise the `Debug` form, otherwise the type name.
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+
+    /// The custom message, if one was given.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The `expect_context!` frames active on the failing thread, outermost first.
+    pub fn context(&self) -> &[String] {
+        &self.context
+    }
+
+    /// The name of the thread that failed, if it has one.
+    pub fn thread(&self) -> Option<&str> {
+        self.thread.as_deref()
+    }
+
+    /// The backtrace of the failure, captured whatever `RUST_BACKTRACE` is set to so a handler can
+    /// forward it. The panic hook only prints it beneath the message when `RUST_LIB_BACKTRACE` (or
+    /// failing that `RUST_BACKTRACE`) is set and not `0`.
+    ///
+    /// Requires the `backtrace` feature.
+    #[cfg(feature = "backtrace")]
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+}
+
+impl ExpectFailure {
+    /// The `Display` form without the context: the custom message, or the expression and error.
+    pub(crate) fn summary(&self) -> Summary<'_> {
+        Summary(self)
+    }
+
+    /// Write the context frames, each on its own line.
+    pub(crate) fn write_context(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for frame in &self.context {
+            write!(f, "\n  while {}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ExpectFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.summary())?;
+        self.write_context(f)
+    }
+}
+
+pub(crate) struct Summary<'a>(&'a ExpectFailure);
+
+impl<'a> fmt::Display for Summary<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let failure = self.0;
+        match failure.message {
+            Some(ref message) => f.write_str(message),
+            None if failure.expr.is_empty() => {
+                write!(f, "`.{}()` failed: {}", failure.macro_name, failure.error)
+            }
+            None => write!(
+                f,
+                "`{}!({})` failed: {}",
+                failure.macro_name, failure.expr, failure.error
+            ),
+        }
+    }
+}
+
+/// The parts of a failure known when the macro is expanded.
+#[doc(hidden)]
+pub struct Site {
+    pub module_path: &'static str,
+    pub macro_name: &'static str,
+    pub expr: &'static str,
+    pub format: Option<Format>,
+}
+
+/// Build the `ExpectFailure` and pass it to the installed handler.
+///
+/// Every macro routes its failure branch here. It is cold, never inlined and not generic, so each
+/// call site only pays for a branch and a call, not a copy of the formatting machinery.
+#[doc(hidden)]
+#[cold]
+#[inline(never)]
+#[track_caller]
+pub fn fail(site: &'static Site, error: ErrorRef, message: Option<fmt::Arguments>) -> ! {
+    let location = Location::caller();
+    let failure = ExpectFailure {
+        file: location.file(),
+        line: location.line(),
+        column: location.column(),
+        module_path: site.module_path,
+        macro_name: site.macro_name,
+        expr: site.expr,
+        error: error
+            .render(site.format.unwrap_or_else(format::format))
+            .to_string(),
+        message: message.map(|m| m.to_string()),
+        context: context::render(),
+        thread: thread::current().name().map(String::from),
+        #[cfg(feature = "backtrace")]
+        backtrace: Arc::new(Backtrace::force_capture()),
+    };
+    match handler::handler() {
+        Some(handler) => handler(&failure),
+        // Not through `default_handler`: a call through a fn pointer loses `#[track_caller]`, and
+        // the panic hook should see the location of the macro, not of this crate.
+        None => {
+            install_panic_hook();
+            panic::panic_any(failure)
+        }
+    }
+}
+
+/// Render `error` and fail, for the bare `expect!(result)`.
+///
+/// `render` is the formatting the macro selected for `E`, as a function pointer so there is one
+/// copy of this per error type rather than per call site. It is cold and never inlined like
+/// `fail`.
+#[doc(hidden)]
+#[cold]
+#[inline(never)]
+#[track_caller]
+pub fn fail_render<E>(
+    site: &'static Site,
+    error: E,
+    render: for<'e> fn(&'e E) -> ErrorRef<'e>,
+) -> ! {
+    fail(site, render(&error), None)
+}
+
+/// Install a panic hook that prints `ExpectFailure` payloads the way the default hook prints
+/// string payloads, i.e. `thread 'main' panicked at src/main.rs:4:5:` followed by the message.
+/// Every other payload is passed to the hook that was installed before.
+///
+/// The first failure that panics installs it, so there is usually no need to call this. A hook
+/// set later replaces it and only sees a `Box<dyn Any>`: to keep a hook of your own for the other
+/// panics, set it first and call this right after. Calling it more than once has no effect.
+///
+/// The payload is not a string, so `#[should_panic(expected = "..")]` cannot match it. Use a plain
+/// `#[should_panic]`, or `catch_unwind` and check the [`ExpectFailure`] fields.
+///
+/// ```rust
+/// #[macro_use] extern crate expect_macro;
+/// use std::panic;
+///
+/// # fn main() {
+/// panic::set_hook(Box::new(|info| eprintln!("custom hook: {}", info)));
+/// expect_macro::install_panic_hook();
+/// let port: u16 = expect!("8080".parse());
+/// # }
+/// ```
+///
+/// [`ExpectFailure`]: struct.ExpectFailure.html
+pub fn install_panic_hook() {
+    static HOOK: Once = Once::new();
+    if thread::panicking() {
+        return;
+    }
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            match info.payload().downcast_ref::<ExpectFailure>() {
+                Some(failure) => report(failure),
+                None => previous(info),
+            }
+        }));
+    });
+}
+
+fn report(failure: &ExpectFailure) {
+    static NOTED: AtomicBool = AtomicBool::new(false);
+    let thread = thread::current();
+    eprintln!(
+        "thread '{}' panicked at {}:{}:{}:\n{}",
+        thread.name().unwrap_or("<unnamed>"),
+        failure.file,
+        failure.line,
+        failure.column,
+        failure,
+    );
+    if backtrace_enabled() {
+        #[cfg(feature = "backtrace")]
+        eprintln!("stack backtrace:\n{}", failure.backtrace);
+        #[cfg(not(feature = "backtrace"))]
+        eprintln!("stack backtrace:\n{}", Backtrace::force_capture());
+    } else if !NOTED.swap(true, Ordering::Relaxed) {
+        eprintln!("note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace");
+    }
+}
+
+/// Whether a backtrace should be printed. With the `backtrace` feature `RUST_LIB_BACKTRACE` takes
+/// precedence, like it does for `Backtrace::capture`.
+fn backtrace_enabled() -> bool {
+    #[cfg(feature = "backtrace")]
+    {
+        if let Ok(v) = env::var("RUST_LIB_BACKTRACE") {
+            return v != "0";
+        }
+    }
+    env::var("RUST_BACKTRACE")
+        .map(|v| v != "0")
+        .unwrap_or(false)
+}
@@ -0,0 +1,119 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! How the error of a failure is formatted.
+
+use std::env;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How the error of a bare `expect!(result)` is formatted.
+///
+/// A single call can choose with `expect!(result; debug)`, `expect!(result; pretty)` or
+/// `expect!(result; display)`. Every other call uses the process-wide default, see
+/// [`set_format`].
+///
+/// [`set_format`]: fn.set_format.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The `Display` and `source()` chain of a `std::error::Error`, otherwise `{:?}`. The default.
+    Auto,
+    /// `{:?}`.
+    Debug,
+    /// `{:#?}`.
+    Pretty,
+    /// The `Display` (and `source()` chain) of errors that have one, otherwise `{:?}`.
+    Display,
+}
+
+const UNSET: u8 = 0;
+
+/// The process-wide `Format` plus one, or `UNSET` until it is read from the environment.
+static FORMAT: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Set the process-wide default `Format`.
+///
+/// Until this is called the default is read from the `EXPECT_MACRO_FORMAT` environment variable,
+/// which can be `auto`, `debug`, `pretty` or `display`. It is [`Format::Auto`] if that is unset
+/// or invalid.
+///
+/// [`Format::Auto`]: enum.Format.html#variant.Auto
+pub fn set_format(format: Format) {
+    FORMAT.store(format as u8 + 1, Ordering::Relaxed);
+}
+
+/// The process-wide default `Format`.
+pub fn format() -> Format {
+    match FORMAT.load(Ordering::Relaxed) {
+        UNSET => {
+            let format = env::var("EXPECT_MACRO_FORMAT")
+                .ok()
+                .and_then(|v| parse(&v))
+                .unwrap_or(Format::Auto);
+            // Don't overwrite a racing `set_format`.
+            let _ = FORMAT.compare_exchange(
+                UNSET,
+                format as u8 + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+            self::format()
+        }
+        1 => Format::Auto,
+        2 => Format::Debug,
+        3 => Format::Pretty,
+        _ => Format::Display,
+    }
+}
+
+fn parse(format: &str) -> Option<Format> {
+    match format.trim().to_ascii_lowercase().as_str() {
+        "auto" => Some(Format::Auto),
+        "debug" => Some(Format::Debug),
+        "pretty" => Some(Format::Pretty),
+        "display" => Some(Format::Display),
+        _ => None,
+    }
+}
+
+/// Whether mismatched values are diffed, `UNSET` until it is read from the environment.
+static DIFF: AtomicU8 = AtomicU8::new(UNSET);
+
+const DIFF_OFF: u8 = 1;
+const DIFF_ON: u8 = 2;
+
+/// Set whether `expect_eq!` and `expect_cmp!(a, ==, b)` print a [`LineDiff`] of mismatched values
+/// whose `{:#?}` spans several lines. Otherwise both values are printed in full, which is easier
+/// for tools to parse.
+///
+/// Until this is called it is read from the `EXPECT_MACRO_DIFF` environment variable: `0`, `off`
+/// or `false` disable it. It is enabled by default.
+///
+/// [`LineDiff`]: struct.LineDiff.html
+pub fn set_diff(enabled: bool) {
+    DIFF.store(if enabled { DIFF_ON } else { DIFF_OFF }, Ordering::Relaxed);
+}
+
+/// Whether mismatched values are diffed, see [`set_diff`].
+///
+/// [`set_diff`]: fn.set_diff.html
+pub fn diff_enabled() -> bool {
+    match DIFF.load(Ordering::Relaxed) {
+        UNSET => {
+            let enabled = match env::var("EXPECT_MACRO_DIFF") {
+                Ok(v) => match v.trim().to_ascii_lowercase().as_str() {
+                    "0" | "off" | "false" => DIFF_OFF,
+                    _ => DIFF_ON,
+                },
+                Err(_) => DIFF_ON,
+            };
+            // Don't overwrite a racing `set_diff`.
+            let _ = DIFF.compare_exchange(UNSET, enabled, Ordering::Relaxed, Ordering::Relaxed);
+            diff_enabled()
+        }
+        state => state == DIFF_ON,
+    }
+}
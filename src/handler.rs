@@ -0,0 +1,139 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! The pluggable behaviour of a failed `expect!`.
+
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::panic;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use failure::{install_panic_hook, ExpectFailure};
+
+/// What to do with a failed `expect!`. It must not return.
+pub type Handler = fn(&ExpectFailure) -> !;
+
+/// The handler installed with `set_handler`, or null for `default_handler`.
+static HANDLER: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+thread_local! {
+    /// The innermost `with_handler` on this thread, which takes precedence over `HANDLER`.
+    static SCOPED: Cell<Option<Handler>> = const { Cell::new(None) };
+}
+
+/// Install the handler every failed `expect!` (and friends) is routed through.
+///
+/// This can only be done once per process, and should be done early in `main`. Without it
+/// [`default_handler`] is used.
+///
+/// ```rust,no_run
+/// use expect_macro::ExpectFailure;
+/// use std::process;
+///
+/// fn log_and_abort(failure: &ExpectFailure) -> ! {
+///     eprintln!("{}:{}: {}", failure.file(), failure.line(), failure);
+///     process::abort()
+/// }
+///
+/// fn main() {
+///     expect_macro::set_handler(log_and_abort).unwrap();
+/// }
+/// ```
+///
+/// [`default_handler`]: fn.default_handler.html
+pub fn set_handler(handler: Handler) -> Result<(), SetHandlerError> {
+    HANDLER
+        .compare_exchange(
+            ptr::null_mut(),
+            handler as *mut (),
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        )
+        .map(|_| ())
+        .map_err(|_| SetHandlerError(()))
+}
+
+/// Use `handler` for failures on the current thread while `f` runs.
+///
+/// This takes precedence over [`set_handler`] and does not affect other threads, so a test can
+/// record its failures without changing the tests `cargo test` runs in parallel. Scopes can be
+/// nested; the previous handler is restored when `f` returns or unwinds.
+///
+/// ```rust
+/// #[macro_use] extern crate expect_macro;
+/// use expect_macro::ExpectFailure;
+/// use std::panic;
+///
+/// struct Recorded(String);
+///
+/// fn record(failure: &ExpectFailure) -> ! {
+///     panic::resume_unwind(Box::new(Recorded(failure.to_string())))
+/// }
+///
+/// # fn main() {
+/// let payload = panic::catch_unwind(|| {
+///     expect_macro::with_handler(record, || expect!(None::<u32>, "need a value"))
+/// }).unwrap_err();
+/// assert_eq!(payload.downcast_ref::<Recorded>().unwrap().0, "need a value");
+/// # }
+/// ```
+///
+/// [`set_handler`]: fn.set_handler.html
+pub fn with_handler<F: FnOnce() -> R, R>(handler: Handler, f: F) -> R {
+    struct Restore(Option<Handler>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED.with(|scoped| scoped.set(self.0));
+        }
+    }
+
+    let _restore = Restore(SCOPED.with(|scoped| scoped.replace(Some(handler))));
+    f()
+}
+
+/// The handler used unless another is installed: `panic!` with the `ExpectFailure` as payload.
+///
+/// The panic goes through the process panic hook like any other, which [`install_panic_hook`]
+/// (done on the first failure) extends to print it readably.
+///
+/// [`install_panic_hook`]: fn.install_panic_hook.html
+#[track_caller]
+pub fn default_handler(failure: &ExpectFailure) -> ! {
+    install_panic_hook();
+    panic::panic_any(failure.clone())
+}
+
+/// The handler to use on this thread, or `None` for `default_handler`. This never blocks: it is a
+/// thread local read and, if no `with_handler` is active, a single atomic load.
+pub(crate) fn handler() -> Option<Handler> {
+    if let Ok(Some(handler)) = SCOPED.try_with(Cell::get) {
+        return Some(handler);
+    }
+    let handler = HANDLER.load(Ordering::Acquire);
+    if handler.is_null() {
+        None
+    } else {
+        // Only ever set from a `Handler` in `set_handler`.
+        Some(unsafe { mem::transmute::<*mut (), Handler>(handler) })
+    }
+}
+
+/// The error returned by `set_handler` when a handler is already installed.
+#[derive(Debug)]
+pub struct SetHandlerError(());
+
+impl fmt::Display for SetHandlerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an expect_macro handler is already installed")
+    }
+}
+
+impl Error for SetHandlerError {}
@@ -13,11 +13,19 @@
 //! - Includes the exact line number of the error
 //! - Allows you to specify a custom error message with formatting.
 //! - Lazy evaluates error conditions (unlike `result.expect(&format!(...))`)
+//! - Panics with an [`ExpectFailure`] payload, so `catch_unwind` users can inspect each field.
+//! - Lets the process decide what a failure does instead of panicking, see [`set_handler`].
+//! - Reports the [`expect_context!`] frames active on the failing thread.
+//!
+//! A failure that panics prints a backtrace like any other panic when `RUST_BACKTRACE` is set.
+//! The `backtrace` feature additionally captures a backtrace in every [`ExpectFailure`], so
+//! failure handlers can forward one even when `RUST_BACKTRACE` is not set, and lets
+//! `RUST_LIB_BACKTRACE` take precedence over `RUST_BACKTRACE` for failures.
 //!
 //! This gives you panic messages like this:
 //!
 //! ```no_compile
-//! thread 'example' panicked at '"expect error"', src/lib.rs:5:5
+//! thread 'example' panicked at '`expect!(result)` failed: "expect error"', src/lib.rs:5:5
 //! ```
 //!
 //! As opposed to:
@@ -26,30 +34,53 @@
 //! thread 'example' panicked at 'called `Result::unwrap()` on an `Err` value: "expect error"', libcore/result.rs:945:5
 //! ```
 //!
-//! # Alternatives
+//! [`ExpectFailure`]: struct.ExpectFailure.html
+//! [`expect_context!`]: macro.expect_context.html
+//! [`set_handler`]: fn.set_handler.html
+//!
+//! # Referencing the error
 //!
-//! If you need to include the `Err` in a custom error message then do this instead:
+//! If you need to include the `Err` in a custom error message then bind it with `name =>`:
 //!
 //! ```rust,should_panic
+//! #[macro_use] extern crate expect_macro;
+//!
 //! # fn main() {
-//! let result = Err("expect error");
-//! result.unwrap_or_else(|err| panic!("Got {} but expected 42", err));
+//! let result: Result<u32, &str> = Err("expect error");
+//! expect!(result, err => "Got {} but expected 42", err);
 //! # }
 //! ```
 
 /// Unwrap a result or `panic!` with a message.
 ///
-/// Works with [`Result`] and [`Option`].
+/// Works with [`Result`], [`Option`], `bool`, `Poll` and `ControlFlow`, or anything else
+/// implementing [`IntoResult`].
 ///
-/// This macro has two forms:
+/// This macro has four forms:
 ///
-/// - `expect!(result)`: calls `panic!("{:#?}", err)` on any unwrapped `Err`/`None`.
+/// - `expect!(result)`: calls `panic!("{:?}", err)` on any unwrapped `Err`/`None`, prefixed by
+///   the stringified `result` so you can tell which call failed. If `err` implements
+///   `std::error::Error`, or is a `Box<dyn Error>`, its `Display` is used instead, followed by
+///   every `source()` under "Caused by:". If it implements neither, its type name is printed, so
+///   `expect!` works with any `Result`. The `Box<dyn Any + Send>` of `thread::Result` and
+///   `catch_unwind` is printed as the panic message it holds, including the thread and location
+///   of a failed `expect!`. The process-wide default can be changed with [`set_format`] or the
+///   `EXPECT_MACRO_FORMAT` environment variable.
+/// - `expect!(result; debug)`, `expect!(result; pretty)` or `expect!(result; display)`: like the
+///   above, but format `err` with `{:?}`, `{:#?}` or `{}` respectively. Like [`Format::Display`],
+///   `display` is followed by the "Caused by:" chain of a `std::error::Error`.
 /// - `expect!(result, ...)`: calls `panic!(...)` on any unwrapped `Err`/`None`, allowing you to
 ///   specify your own error formatting. This is recommened when you are using `expect!` with
-///   [`Option`]
+///   [`Option`]. A single message that is not a string literal, such as a `&str` variable or a
+///   `String`, is printed as is.
+/// - `expect!(result, err => ...)`: like the above, but binds the `Err`/`None` value to `err` so
+///   the format arguments can use it.
 ///
 /// [`Result`]: https://doc.rust-lang.org/std/result/enum.Result.html
 /// [`Option`]: https://doc.rust-lang.org/std/option/enum.Option.html
+/// [`set_format`]: fn.set_format.html
+/// [`Format::Display`]: enum.Format.html#variant.Display
+/// [`IntoResult`]: trait.IntoResult.html
 ///
 /// # Example
 ///
@@ -65,7 +96,7 @@
 /// # }
 ///
 /// // COMPILER OUTPUT:
-/// // thread 'example' panicked at '"expect error"', src/lib.rs:5:5
+/// // thread 'example' panicked at '`expect!(result)` failed: "expect error"', src/lib.rs:5:5
 /// ```
 ///
 /// With format
@@ -82,83 +113,1891 @@
 /// // COMPILER OUTPUT:
 /// // thread 'example' panicked at 'Some values: 1, 2', src/lib.rs:5:5
 /// ```
+///
+/// With format referencing the error
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+/// use expect_macro::*;
+///
+/// # fn main() {
+/// let path = "/etc/x.toml";
+/// let result: Result<(), &str> = Err("permission denied");
+/// expect!(result, err => "failed to open {}: {}", path, err);
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at 'failed to open /etc/x.toml: permission denied', src/lib.rs:7:5
+/// ```
 #[macro_export]
 macro_rules! expect {
+    [$result:expr, $err:ident => $($rest:tt)*] => {
+        match $crate::IntoResult::__into_result($result, stringify!($result)) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err($err) => $crate::__private::fail(
+                $crate::__expect_site!("expect", $result),
+                $crate::__expect_error_ref!(&$err),
+                ::std::option::Option::Some($crate::__expect_message!($($rest)*)),
+            ),
+        }
+    };
     [$result:expr, $($rest:tt)*] => {
-        $crate::IntoResult::into_result($result).unwrap_or_else(|_| {
-            panic!($($rest)*)
-        })
+        match $crate::IntoResult::__into_result($result, stringify!($result)) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err(e) => $crate::__private::fail(
+                $crate::__expect_site!("expect", $result),
+                $crate::__expect_error_ref!(&e),
+                ::std::option::Option::Some($crate::__expect_message!($($rest)*)),
+            ),
+        }
+    };
+    [$result:expr; debug] => {
+        match $crate::IntoResult::__into_result($result, stringify!($result)) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err(e) => $crate::__private::fail(
+                $crate::__expect_site!(
+                    "expect",
+                    $result,
+                    ::std::option::Option::Some($crate::Format::Debug)
+                ),
+                $crate::__private::ErrorRef::Debug(&e),
+                ::std::option::Option::None,
+            ),
+        }
+    };
+    [$result:expr; pretty] => {
+        match $crate::IntoResult::__into_result($result, stringify!($result)) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err(e) => $crate::__private::fail(
+                $crate::__expect_site!(
+                    "expect",
+                    $result,
+                    ::std::option::Option::Some($crate::Format::Pretty)
+                ),
+                $crate::__private::ErrorRef::Debug(&e),
+                ::std::option::Option::None,
+            ),
+        }
+    };
+    [$result:expr; display] => {
+        match $crate::IntoResult::__into_result($result, stringify!($result)) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err(e) => $crate::__private::fail(
+                $crate::__expect_site!(
+                    "expect",
+                    $result,
+                    ::std::option::Option::Some($crate::Format::Display)
+                ),
+                {
+                    #[allow(unused_imports)]
+                    use $crate::__private::{BoxErrorKind, DisplayKind, ErrorKind, PayloadKind};
+                    (&&&&$crate::__private::Wrap(&e)).__expect_error_ref()
+                },
+                ::std::option::Option::None,
+            ),
+        }
+    };
+    [$result:expr] => {{
+        let site = $crate::__expect_site!("expect", $result);
+        match $crate::IntoResult::__expect($result, site) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err(e) => {
+                $crate::__private::fail_render(site, e, |e| $crate::__expect_error_ref!(e))
+            }
+        }
+    }};
+}
+
+/// Unwrap the error of a result or `panic!` with a message.
+///
+/// The opposite of [`expect!`]: use it when a call _must_ fail. Works with anything `expect!`
+/// works with, and returns the `Err` value.
+///
+/// - `expect_err!(result)`: panics with the `Debug` of the unexpected `Ok` value.
+/// - `expect_err!(result, ...)`: calls `panic!(...)` on an unexpected `Ok` value.
+///
+/// [`expect!`]: macro.expect.html
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate expect_macro;
+///
+/// # fn main() {
+/// let err = expect_err!("forty-two".parse::<u32>());
+/// assert_eq!(err.to_string(), "invalid digit found in string");
+/// # }
+/// ```
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+///
+/// # fn main() {
+/// expect_err!("42".parse::<u32>());
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at '`expect_err!("42".parse::<u32>())` failed: expected Err, got Ok(42)', src/lib.rs:5:5
+/// ```
+#[macro_export]
+macro_rules! expect_err {
+    [$result:expr, $($rest:tt)*] => {
+        match $crate::IntoResult::into_result($result) {
+            ::std::result::Result::Err(e) => e,
+            ::std::result::Result::Ok(v) => $crate::__private::fail(
+                $crate::__expect_site!("expect_err", $result),
+                $crate::__expect_error_ref!(&v),
+                ::std::option::Option::Some($crate::__expect_message!($($rest)*)),
+            ),
+        }
     };
     [$result:expr] => {
-        $crate::IntoResult::into_result($result).unwrap_or_else(|e| {
-            panic!("{:?}", e)
-        })
+        match $crate::IntoResult::into_result($result) {
+            ::std::result::Result::Err(e) => e,
+            ::std::result::Result::Ok(v) => $crate::__private::fail(
+                $crate::__expect_site!("expect_err", $result),
+                $crate::__private::ErrorRef::Debug(&$crate::__private::Unexpected {
+                    expected: "Err",
+                    found: "Ok",
+                    value: $crate::__expect_error_ref!(&v),
+                }),
+                ::std::option::Option::None,
+            ),
+        }
     };
 }
 
-/// Used to ensure either `Option` or `Result` are the `Result` type.
-pub trait IntoResult<T, E> {
-    fn into_result(self) -> Result<T, E>;
+/// Assert that an option is `None` or `panic!` with a message.
+///
+/// Like [`expect_err!`] this works with anything `expect!` works with, but discards the error.
+///
+/// - `expect_none!(option)`: panics with the `Debug` of the unexpected `Some` value.
+/// - `expect_none!(option, ...)`: calls `panic!(...)` on an unexpected `Some` value.
+///
+/// [`expect_err!`]: macro.expect_err.html
+///
+/// # Example
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+///
+/// # fn main() {
+/// let cache = vec![1, 2, 3];
+/// expect_none!(cache.iter().find(|&&x| x == 2));
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at '`expect_none!(cache.iter().find(|&&x| x == 2))` failed: expected None, got Some(2)', src/lib.rs:6:5
+/// ```
+#[macro_export]
+macro_rules! expect_none {
+    [$option:expr, $($rest:tt)*] => {
+        match $crate::IntoResult::into_result($option) {
+            ::std::result::Result::Err(_) => (),
+            ::std::result::Result::Ok(v) => $crate::__private::fail(
+                $crate::__expect_site!("expect_none", $option),
+                $crate::__expect_error_ref!(&v),
+                ::std::option::Option::Some($crate::__expect_message!($($rest)*)),
+            ),
+        }
+    };
+    [$option:expr] => {
+        match $crate::IntoResult::into_result($option) {
+            ::std::result::Result::Err(_) => (),
+            ::std::result::Result::Ok(v) => $crate::__private::fail(
+                $crate::__expect_site!("expect_none", $option),
+                $crate::__private::ErrorRef::Debug(&$crate::__private::Unexpected {
+                    expected: "None",
+                    found: "Some",
+                    value: $crate::__expect_error_ref!(&v),
+                }),
+                ::std::option::Option::None,
+            ),
+        }
+    };
 }
 
-impl<T, E> IntoResult<T, E> for Result<T, E> {
-    fn into_result(self) -> Result<T, E> {
-        self
-    }
+/// Add a frame of context to every failure on this thread while the returned guard lives.
+///
+/// Takes the same arguments as `format!`, but they are only formatted if something fails. Each
+/// frame is printed beneath the failure message, outermost first, prefixed with "while".
+///
+/// The arguments are borrowed, not moved, so they stay usable and can be references such as
+/// `path.display()`. The frame is removed when the returned [`ContextGuard`] is dropped, so bind
+/// it for as long as the frame should apply: `let _ctx = expect_context!(...);`. The guard is a
+/// temporary of the enclosing block, like the value of `pin!`, and the macro returns a reference
+/// to it, so the frame is removed at the end of that block at the latest.
+///
+/// # Example
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+/// use std::path::Path;
+///
+/// # fn main() {
+/// let path = Path::new("/etc/x.toml");
+/// let _ctx = expect_context!("loading config {}", path.display());
+/// {
+///     let _ctx = expect_context!("parsing section [{}]", "db");
+///     expect!(None::<u16>, "missing port");
+/// }
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at 'missing port
+/// //   while loading config /etc/x.toml
+/// //   while parsing section [db]', src/lib.rs:9:5
+/// ```
+///
+/// [`ContextGuard`]: struct.ContextGuard.html
+#[macro_export]
+macro_rules! expect_context {
+    ($($args:tt)+) => {
+        &$crate::ContextGuard::push(|f: &mut ::std::fmt::Formatter| {
+            f.write_fmt($crate::__expect_message!($($args)+))
+        })
+    };
 }
 
-impl<T> IntoResult<T, &'static str> for Option<T> {
-    fn into_result(self) -> Result<T, &'static str> {
-        match self {
-            Some(v) => Ok(v),
-            None => Err("Got value of None"),
+/// Unwrap a nested result or `panic!` with a message saying which layer failed.
+///
+/// Works with any two nested [`IntoResult`] types, e.g. `Option<Result<T, E>>` from
+/// `iterator.next().map(parse)`, `Result<Option<T>, E>` or `Poll<Result<T, E>>`, and takes the
+/// same forms as
+/// [`expect!`]. This replaces `expect!(expect!(x))`, which reports the two layers separately.
+///
+/// [`IntoResult`]: trait.IntoResult.html
+/// [`expect!`]: macro.expect.html
+///
+/// # Example
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+///
+/// # fn main() {
+/// let mut args = vec!["forty-two"].into_iter();
+/// let n: u32 = expect_flat!(args.next().map(str::parse));
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at '`expect_flat!(args.next().map(str::parse))` failed: inner layer failed: invalid digit found in string', src/lib.rs:5:14
+/// ```
+#[macro_export]
+macro_rules! expect_flat {
+    [$result:expr, $err:ident => $($rest:tt)*] => {
+        match $crate::flatten($result) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err($err) => $crate::__private::fail(
+                $crate::__expect_site!("expect_flat", $result),
+                $crate::__expect_error_ref!(&$err),
+                ::std::option::Option::Some($crate::__expect_message!($($rest)*)),
+            ),
         }
-    }
+    };
+    [$result:expr, $($rest:tt)*] => {
+        match $crate::flatten($result) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err(e) => $crate::__private::fail(
+                $crate::__expect_site!("expect_flat", $result),
+                $crate::__expect_error_ref!(&e),
+                ::std::option::Option::Some($crate::__expect_message!($($rest)*)),
+            ),
+        }
+    };
+    [$result:expr] => {
+        match $crate::flatten($result) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err(e) => $crate::__private::fail(
+                $crate::__expect_site!("expect_flat", $result),
+                $crate::__expect_error_ref!(&e),
+                ::std::option::Option::None,
+            ),
+        }
+    };
 }
 
-#[test]
-#[should_panic]
-fn expect_panic_bare() {
-    expect!(Err("expect error"));
+/// Bind a pattern or `panic!` with a message.
+///
+/// `expect_let!(Pattern = value)` is `let Pattern = value else { panic!(...) };`, binding the
+/// variables of the pattern in the enclosing block, so it must be used as a statement. Use it for
+/// enums that are not a `Result` or `Option`.
+///
+/// Like `let`-`else`, a variable or field such as `msg` or `self.msg` is matched in place, so
+/// `ref` patterns borrow from it and leave it usable afterwards. Any other expression is
+/// evaluated once into a temporary.
+///
+/// - `expect_let!(pattern = value)`: panics with the pattern and the `Debug` of the value that
+///   did not match it.
+/// - `expect_let!(pattern = value, ...)`: calls `panic!(...)` if the value did not match.
+///
+/// # Example
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+///
+/// #[derive(Debug)]
+/// enum Msg {
+///     Data(Vec<u8>),
+///     Ping,
+/// }
+///
+/// # fn main() {
+/// let msg = Msg::Ping;
+/// expect_let!(Msg::Data(bytes) = msg);
+/// assert!(bytes.is_empty());
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at '`expect_let!(Msg::Data(bytes) = msg)` failed: expected Msg::Data(bytes), got Ping', src/lib.rs:11:1
+/// ```
+#[macro_export]
+macro_rules! expect_let {
+    [$($pat:pat_param)|+ = $place:ident $(. $field:tt)*, $($rest:tt)*] => {
+        #[allow(unused_parens)]
+        let ($($pat)|+) = $place $(. $field)* else {
+            $crate::__private::fail(
+                $crate::__expect_site!("expect_let", let $($pat)|+ = $place $(. $field)*),
+                $crate::__expect_error_ref!(&$place $(. $field)*),
+                ::std::option::Option::Some($crate::__expect_message!($($rest)*)),
+            )
+        };
+    };
+    [$($pat:pat_param)|+ = $place:ident $(. $field:tt)*] => {
+        #[allow(unused_parens)]
+        let ($($pat)|+) = $place $(. $field)* else {
+            $crate::__private::fail(
+                $crate::__expect_site!("expect_let", let $($pat)|+ = $place $(. $field)*),
+                $crate::__private::ErrorRef::Debug(&$crate::__private::Mismatch {
+                    pattern: stringify!($($pat)|+),
+                    value: $crate::__expect_error_ref!(&$place $(. $field)*),
+                }),
+                ::std::option::Option::None,
+            )
+        };
+    };
+    [$($pat:pat_param)|+ = $value:expr, $($rest:tt)*] => {
+        let value = $value;
+        #[allow(unused_parens)]
+        let ($($pat)|+) = value else {
+            $crate::__private::fail(
+                $crate::__expect_site!("expect_let", let $($pat)|+ = $value),
+                $crate::__expect_error_ref!(&value),
+                ::std::option::Option::Some($crate::__expect_message!($($rest)*)),
+            )
+        };
+    };
+    [$($pat:pat_param)|+ = $value:expr] => {
+        let value = $value;
+        #[allow(unused_parens)]
+        let ($($pat)|+) = value else {
+            $crate::__private::fail(
+                $crate::__expect_site!("expect_let", let $($pat)|+ = $value),
+                $crate::__private::ErrorRef::Debug(&$crate::__private::Mismatch {
+                    pattern: stringify!($($pat)|+),
+                    value: $crate::__expect_error_ref!(&value),
+                }),
+                ::std::option::Option::None,
+            )
+        };
+    };
 }
 
-#[test]
-#[should_panic]
-fn regular_panic_bare() {
-    let result: Result<(), &str> = Err("expect error");
-    result.unwrap();
+/// Match a value against a pattern and return a projection of it, or `panic!` with a message.
+///
+/// `expect_matches!(value, Pattern if guard => projection)` is a `match` with a single arm: the
+/// guard and projection are only evaluated when the pattern matches. The guard is optional, and
+/// the pattern may have several alternatives, i.e. `Ok(n) | Err(n)`.
+///
+/// - `expect_matches!(value, pattern => projection)`: panics with the pattern and the `Debug` of
+///   the value that did not match it.
+/// - `expect_matches!(value, pattern => projection, ...)`: calls `panic!(...)` if the value did
+///   not match.
+///
+/// # Example
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+/// use std::net::SocketAddr;
+///
+/// # fn main() {
+/// let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+/// let port = expect_matches!(addr, SocketAddr::V4(a) if a.port() != 0 => a.port());
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at '`expect_matches!(addr, SocketAddr::V4(a) if a.port() != 0 => a.port())` failed: expected SocketAddr::V4(a) if a.port() != 0, got 127.0.0.1:0', src/lib.rs:6:12
+/// ```
+#[macro_export]
+macro_rules! expect_matches {
+    [$value:expr, $($pat:pat_param)|+ $(if $guard:expr)? => $proj:expr, $($rest:tt)+] => {
+        match $value {
+            $($pat)|+ $(if $guard)? => $proj,
+            value => $crate::__private::fail(
+                $crate::__expect_site!(
+                    @expr "expect_matches",
+                    concat!(stringify!($value), ", ", stringify!($($pat)|+ $(if $guard)? => $proj))
+                ),
+                $crate::__expect_error_ref!(&value),
+                ::std::option::Option::Some($crate::__expect_message!($($rest)*)),
+            ),
+        }
+    };
+    [$value:expr, $($pat:pat_param)|+ $(if $guard:expr)? => $proj:expr] => {
+        match $value {
+            $($pat)|+ $(if $guard)? => $proj,
+            value => $crate::__private::fail(
+                $crate::__expect_site!(
+                    @expr "expect_matches",
+                    concat!(stringify!($value), ", ", stringify!($($pat)|+ $(if $guard)? => $proj))
+                ),
+                $crate::__private::ErrorRef::Debug(&$crate::__private::Mismatch {
+                    pattern: stringify!($($pat)|+ $(if $guard)?),
+                    value: $crate::__expect_error_ref!(&value),
+                }),
+                ::std::option::Option::None,
+            ),
+        }
+    };
 }
 
-#[test]
-#[should_panic]
-fn expect_panic_msg() {
-    expect!(Err("expect error"), "Some values: {}, {}", 1, 2);
+/// Assert that two values are equal and return the left one, or `panic!` with a message.
+///
+/// Like `assert_eq!`, but returns the left value so it does not need a temporary binding. Both
+/// values must implement `Debug`. If either value's `{:#?}` spans several lines the message is a
+/// [`LineDiff`] of them instead, unless that is disabled with [`set_diff`].
+///
+/// - `expect_eq!(left, right)`: panics with the `Debug` of both values.
+/// - `expect_eq!(left, right, ...)`: calls `panic!(...)` if they are not equal.
+///
+/// # Example
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+///
+/// # fn main() {
+/// let n: u32 = expect_eq!("42".parse().unwrap(), 42);
+/// expect_eq!(n + 1, 42);
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at '`expect_eq!(n + 1, 42)` failed: expected left == right
+/// //   left: 43
+/// //  right: 42', src/lib.rs:6:1
+/// ```
+///
+/// [`LineDiff`]: struct.LineDiff.html
+/// [`set_diff`]: fn.set_diff.html
+#[macro_export]
+macro_rules! expect_eq {
+    [$left:expr, $right:expr, $($rest:tt)+] => {
+        $crate::__expect_compare!(
+            "expect_eq",
+            concat!(stringify!($left), ", ", stringify!($right)),
+            $left, ==, $right,
+            ::std::option::Option::Some($crate::__expect_message!($($rest)+))
+        )
+    };
+    [$left:expr, $right:expr $(,)?] => {
+        $crate::__expect_compare!(
+            "expect_eq",
+            concat!(stringify!($left), ", ", stringify!($right)),
+            $left, ==, $right,
+            ::std::option::Option::None
+        )
+    };
 }
 
-#[test]
-#[should_panic]
-fn sanity_chain() {
-    fn foo(a: ()) -> Result<(), &'static str> {
-        Ok(a)
-    }
+/// Assert that two values are not equal and return the left one, or `panic!` with a message.
+///
+/// The opposite of [`expect_eq!`], taking the same forms.
+///
+/// [`expect_eq!`]: macro.expect_eq.html
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate expect_macro;
+///
+/// # fn main() {
+/// let port = expect_ne!(8080u16, 0, "port must be assigned");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! expect_ne {
+    [$left:expr, $right:expr, $($rest:tt)+] => {
+        $crate::__expect_compare!(
+            "expect_ne",
+            concat!(stringify!($left), ", ", stringify!($right)),
+            $left, !=, $right,
+            ::std::option::Option::Some($crate::__expect_message!($($rest)+))
+        )
+    };
+    [$left:expr, $right:expr $(,)?] => {
+        $crate::__expect_compare!(
+            "expect_ne",
+            concat!(stringify!($left), ", ", stringify!($right)),
+            $left, !=, $right,
+            ::std::option::Option::None
+        )
+    };
+}
 
-    #[allow(unused_variables)]
-    fn bar(a: ()) -> Result<(), &'static str> {
-        Err("expect error")
-    }
+/// Assert a comparison of two values and return the left one, or `panic!` with a message.
+///
+/// `expect_cmp!(left, op, right)` takes any comparison operator (`<`, `<=`, `>`, `>=`, `==` or
+/// `!=`) and the same forms as [`expect_eq!`].
+///
+/// [`expect_eq!`]: macro.expect_eq.html
+///
+/// # Example
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+///
+/// # fn main() {
+/// let len = expect_cmp!(vec![1, 2, 3].len(), <=, 2);
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at '`expect_cmp!(vec![1, 2, 3].len(), <=, 2)` failed: expected left <= right
+/// //   left: 3
+/// //  right: 2', src/lib.rs:5:11
+/// ```
+#[macro_export]
+macro_rules! expect_cmp {
+    [$left:expr, $op:tt, $right:expr, $($rest:tt)+] => {
+        $crate::__expect_compare!(
+            "expect_cmp",
+            concat!(stringify!($left), ", ", stringify!($op), ", ", stringify!($right)),
+            $left, $op, $right,
+            ::std::option::Option::Some($crate::__expect_message!($($rest)+))
+        )
+    };
+    [$left:expr, $op:tt, $right:expr $(,)?] => {
+        $crate::__expect_compare!(
+            "expect_cmp",
+            concat!(stringify!($left), ", ", stringify!($op), ", ", stringify!($right)),
+            $left, $op, $right,
+            ::std::option::Option::None
+        )
+    };
+}
 
-    let a = ();
-    expect!(bar(expect!(foo(a))));
+/// Compare two values, returning the left one or failing with both.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __expect_compare {
+    ($macro_name:expr, $expr:expr, $left:expr, $op:tt, $right:expr, $message:expr) => {
+        match ($left, &$right) {
+            (left, right) => {
+                if left $op *right {
+                    left
+                } else {
+                    $crate::__private::fail(
+                        $crate::__expect_site!(@expr $macro_name, $expr),
+                        $crate::__private::ErrorRef::Debug(&$crate::__private::Comparison {
+                            op: stringify!($op),
+                            left: &left,
+                            right,
+                        }),
+                        $message,
+                    )
+                }
+            }
+        }
+    };
 }
 
-#[test]
-#[should_panic]
-fn sanity_option_plain() {
-    expect!(None);
+/// Unwrap several results, or `panic!` once listing every one that failed.
+///
+/// `expect_all!(a, b, c)` evaluates every operand and returns the tuple of their `Ok`/`Some`
+/// values. Unlike `(expect!(a), expect!(b), expect!(c))` it does not stop at the first failure:
+/// each failed operand is listed with its stringified expression and error. It takes up to 12
+/// operands of any type [`expect!`] works with.
+///
+/// [`expect!`]: macro.expect.html
+///
+/// # Example
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+/// use std::env;
+///
+/// # fn main() {
+/// let (user, port) = expect_all!(env::var("DB_USER"), "forty-two".parse::<u16>());
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at '`expect_all!(env::var("DB_USER"), "forty-two".parse::<u16>())` failed: 2 operands failed
+/// //   - `env::var("DB_USER")`: environment variable not found
+/// //   - `"forty-two".parse::<u16>()`: invalid digit found in string', src/lib.rs:6:20
+/// ```
+#[macro_export]
+macro_rules! expect_all {
+    [$($result:expr),+ $(,)?] => {{
+        let mut failures = ::std::vec::Vec::new();
+        let values = ($(
+            match $crate::IntoResult::__into_result($result, stringify!($result)) {
+                ::std::result::Result::Ok(v) => ::std::option::Option::Some(v),
+                ::std::result::Result::Err(e) => {
+                    failures.push((
+                        stringify!($result),
+                        $crate::__private::render_operand($crate::__expect_error_ref!(&e)),
+                    ));
+                    ::std::option::Option::None
+                }
+            },
+        )+);
+        if !failures.is_empty() {
+            $crate::__private::fail(
+                $crate::__expect_site!(@expr "expect_all", stringify!($($result),+)),
+                $crate::__private::ErrorRef::Display(&$crate::__private::Failures(&failures)),
+                ::std::option::Option::None,
+            )
+        }
+        $crate::__private::UnwrapAll::unwrap_all(values)
+    }};
 }
 
-#[test]
-#[should_panic]
-fn sanity_option_msg() {
-    expect!(None, "Got None, expected 42");
+/// Unwrap the first result that succeeds, or `panic!` listing every attempt.
+///
+/// `expect_any!(a, b, c)` evaluates its operands lazily, left to right, and returns the `Ok`/`Some`
+/// value of the first one that succeeds; the rest are not evaluated. Only if every operand fails
+/// does it panic, with a numbered list of each attempt's stringified expression and error. The
+/// errors are kept until then, so an attempt that fails before a later one succeeds costs nothing
+/// to render. The operands must have the same success type, but their errors can differ.
+///
+/// # Example
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate expect_macro;
+/// use std::env;
+/// use std::fs;
+///
+/// # fn main() {
+/// let config = expect_any!(
+///     env::var("APP_CONFIG"),
+///     fs::read_to_string("app.toml"),
+///     fs::read_to_string("/etc/app.toml"),
+/// );
+/// # }
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at '`expect_any!(env::var("APP_CONFIG"), fs::read_to_string("app.toml"), fs::read_to_string("/etc/app.toml"))` failed: every attempt failed
+/// //   1. `env::var("APP_CONFIG")`: environment variable not found
+/// //   2. `fs::read_to_string("app.toml")`: No such file or directory (os error 2)
+/// //   3. `fs::read_to_string("/etc/app.toml")`: No such file or directory (os error 2)', src/lib.rs:7:14
+/// ```
+#[macro_export]
+macro_rules! expect_any {
+    [@attempt ($($all:expr),+) [$(($tried:expr, $e:ident))+]] => {
+        $crate::__private::fail(
+            $crate::__expect_site!(@expr "expect_any", stringify!($($all),+)),
+            $crate::__private::ErrorRef::Display(&$crate::__private::Attempts(&[$((
+                stringify!($tried),
+                $crate::__private::render_operand($crate::__expect_error_ref!(&$e)),
+            )),+])),
+            ::std::option::Option::None,
+        )
+    };
+    [@attempt $all:tt [$($tried:tt)*] $result:expr $(, $rest:expr)*] => {
+        match $crate::IntoResult::__into_result($result, stringify!($result)) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err(e) => {
+                $crate::expect_any!(@attempt $all [$($tried)* ($result, e)] $($rest),*)
+            }
+        }
+    };
+    [$($result:expr),+ $(,)?] => {
+        $crate::expect_any!(@attempt ($($result),+) [] $($result),+)
+    };
+}
+
+/// The `Site` of a macro call.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __expect_site {
+    ($macro_name:expr, let $($pat:pat_param)|+ = $expr:expr) => {
+        $crate::__expect_site!(
+            @expr $macro_name,
+            concat!(stringify!($($pat)|+), " = ", stringify!($expr))
+        )
+    };
+    ($macro_name:expr, $expr:expr) => {
+        $crate::__expect_site!($macro_name, $expr, ::std::option::Option::None)
+    };
+    ($macro_name:expr, $expr:expr, $format:expr) => {
+        &$crate::__private::Site {
+            module_path: module_path!(),
+            macro_name: $macro_name,
+            expr: stringify!($expr),
+            format: $format,
+        }
+    };
+    (@expr $macro_name:expr, $expr:expr) => {
+        &$crate::__private::Site {
+            module_path: module_path!(),
+            macro_name: $macro_name,
+            expr: $expr,
+            format: ::std::option::Option::None,
+        }
+    };
+}
+
+/// The custom message of a failure: a format string and its arguments, or a single value such as
+/// a `String`, which is printed with `{}`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __expect_message {
+    ($format:literal $(,)?) => {
+        format_args!($format)
+    };
+    ($message:expr $(,)?) => {
+        format_args!("{}", $message)
+    };
+    ($($args:tt)+) => {
+        format_args!($($args)+)
+    };
+}
+
+/// Borrow an error as an `ErrorRef`, using the most capable formatting it supports.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __expect_error_ref {
+    ($err:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{BoxErrorKind, DebugKind, ErrorKind, OpaqueKind, PayloadKind};
+        (&&&&$crate::__private::Wrap($err)).__expect_error_ref()
+    }};
+}
+
+#[cfg(feature = "derive")]
+extern crate expect_macro_derive;
+
+mod all;
+mod context;
+mod diff;
+mod errors;
+mod ext;
+mod failure;
+mod format;
+mod handler;
+mod render;
+
+pub use context::ContextGuard;
+pub use diff::LineDiff;
+pub use errors::{FalseError, FlatError, NoneError, VariantError};
+#[cfg(feature = "derive")]
+pub use expect_macro_derive::IntoResult;
+pub use ext::ExpectExt;
+pub use failure::{install_panic_hook, ExpectFailure};
+pub use format::{diff_enabled, format, set_diff, set_format, Format};
+pub use handler::{default_handler, set_handler, with_handler, Handler, SetHandlerError};
+
+use std::fmt;
+use std::ops::ControlFlow;
+use std::task::Poll;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use all::{render_operand, Attempts, Failures, UnwrapAll};
+    pub use failure::{fail, fail_render, Site};
+    pub use render::{
+        BoxErrorKind, Comparison, DebugKind, DisplayKind, ErrorKind, ErrorRef, Mismatch,
+        OpaqueKind, PayloadKind, Unexpected, Wrap,
+    };
+}
+
+/// Used to ensure `Option`, `Result`, `bool`, `Poll` and `ControlFlow` are the `Result` type.
+///
+/// With the `derive` feature, `#[derive(IntoResult)]` implements it for enums that behave like a
+/// `Result`. Mark the success variant with `#[into_result(ok)]` (`#[expect]` is taken by the
+/// built-in lint attribute); every other variant is an error, reported as a [`VariantError`]
+/// naming the variant that was hit.
+///
+#[cfg_attr(feature = "derive", doc = "```rust,should_panic")]
+#[cfg_attr(not(feature = "derive"), doc = "```rust,ignore")]
+/// #[macro_use] extern crate expect_macro;
+/// use expect_macro::IntoResult;
+///
+/// #[derive(IntoResult)]
+/// enum Lookup {
+///     #[into_result(ok)]
+///     Found(u32),
+///     Missing(&'static str),
+///     Denied,
+/// }
+///
+/// # fn main() {
+/// assert_eq!(expect!(Lookup::Found(42)), 42);
+/// expect!(Lookup::Missing("key"));
+///
+/// // COMPILER OUTPUT:
+/// // thread 'example' panicked at '`expect!(Lookup::Missing("key"))` failed: expected Lookup::Found, got Lookup::Missing', src/lib.rs:13:1
+/// # }
+/// ```
+///
+/// [`VariantError`]: struct.VariantError.html
+pub trait IntoResult<T, E> {
+    fn into_result(self) -> Result<T, E>;
+
+    /// `into_result` for the bare `expect!(result)`, which fails with `site` itself.
+    ///
+    /// Implementors can override it to fail on a cold path instead of returning an error that
+    /// has to be built at every call site, as `Option` does.
+    #[doc(hidden)]
+    #[inline(always)]
+    #[track_caller]
+    fn __expect(self, _site: &'static __private::Site) -> Result<T, E>
+    where
+        Self: Sized,
+    {
+        self.into_result()
+    }
+
+    /// `into_result` for the macros, which pass the stringified `expr` along for the error types
+    /// that report it, as `bool` does.
+    #[doc(hidden)]
+    #[inline(always)]
+    #[track_caller]
+    fn __into_result(self, _expr: &'static str) -> Result<T, E>
+    where
+        Self: Sized,
+    {
+        self.into_result()
+    }
+
+    /// How the `ExpectExt` methods render the error.
+    ///
+    /// The macros pick this by autoref specialization on the concrete error type, which a
+    /// generic method cannot do. Implementors whose error type is known override it to match.
+    #[doc(hidden)]
+    fn __error_ref<'e>(error: &'e E) -> __private::ErrorRef<'e>
+    where
+        E: fmt::Debug,
+    {
+        __private::ErrorRef::Debug(error)
+    }
+}
+
+impl<T, E> IntoResult<T, E> for Result<T, E> {
+    fn into_result(self) -> Result<T, E> {
+        self
+    }
+}
+
+impl<T> IntoResult<T, NoneError> for Option<T> {
+    #[track_caller]
+    fn into_result(self) -> Result<T, NoneError> {
+        match self {
+            Some(v) => Ok(v),
+            None => Err(errors::none_error::<T>()),
+        }
+    }
+
+    #[inline(always)]
+    #[track_caller]
+    fn __expect(self, site: &'static __private::Site) -> Result<T, NoneError> {
+        match self {
+            Some(v) => Ok(v),
+            None => errors::fail_none::<T>(site),
+        }
+    }
+
+    fn __error_ref<'e>(error: &'e NoneError) -> __private::ErrorRef<'e> {
+        __private::ErrorRef::Error(error)
+    }
+}
+
+/// `Pending` is the error, so `expect!` can assert a hand-written future is ready. Use
+/// `expect_flat!` for a `Poll<Result<T, E>>`.
+impl<T> IntoResult<T, VariantError<Poll<T>>> for Poll<T> {
+    fn into_result(self) -> Result<T, VariantError<Poll<T>>> {
+        match self {
+            Poll::Ready(v) => Ok(v),
+            Poll::Pending => Err(VariantError::new("Poll::Ready", "Poll::Pending", self)),
+        }
+    }
+
+    fn __error_ref<'e>(error: &'e VariantError<Poll<T>>) -> __private::ErrorRef<'e> {
+        __private::ErrorRef::Error(error)
+    }
+}
+
+/// `Continue` is the error, so `expect!` can assert a traversal returned `Break`.
+impl<B, C> IntoResult<B, VariantError<ControlFlow<B, C>>> for ControlFlow<B, C> {
+    fn into_result(self) -> Result<B, VariantError<ControlFlow<B, C>>> {
+        match self {
+            ControlFlow::Break(v) => Ok(v),
+            ControlFlow::Continue(_) => Err(VariantError::new(
+                "ControlFlow::Break",
+                "ControlFlow::Continue",
+                self,
+            )),
+        }
+    }
+
+    fn __error_ref<'e>(error: &'e VariantError<ControlFlow<B, C>>) -> __private::ErrorRef<'e> {
+        __private::ErrorRef::Error(error)
+    }
+}
+
+/// Unwrap both layers of a nested `IntoResult`, such as `Option<Result<T, E>>` or
+/// `Result<Option<T>, E>`, in one step.
+///
+/// ```rust
+/// use expect_macro::{flatten, FlatError};
+///
+/// let nested: Option<Result<u32, &str>> = Some(Err("bad"));
+/// assert_eq!(flatten(nested), Err(FlatError::Inner("bad")));
+/// ```
+#[track_caller]
+pub fn flatten<R, S, T, O, I>(nested: R) -> Result<T, FlatError<O, I>>
+where
+    R: IntoResult<S, O>,
+    S: IntoResult<T, I>,
+{
+    match nested.into_result() {
+        Ok(inner) => inner.into_result().map_err(FlatError::Inner),
+        Err(e) => Err(FlatError::Outer(e)),
+    }
+}
+
+impl IntoResult<(), FalseError> for bool {
+    #[track_caller]
+    fn into_result(self) -> Result<(), FalseError> {
+        if self {
+            Ok(())
+        } else {
+            Err(errors::false_error(None))
+        }
+    }
+
+    #[inline(always)]
+    #[track_caller]
+    fn __expect(self, site: &'static __private::Site) -> Result<(), FalseError> {
+        if self {
+            Ok(())
+        } else {
+            errors::fail_false(site)
+        }
+    }
+
+    #[inline(always)]
+    #[track_caller]
+    fn __into_result(self, expr: &'static str) -> Result<(), FalseError> {
+        if self {
+            Ok(())
+        } else {
+            Err(errors::false_error(Some(expr)))
+        }
+    }
+
+    fn __error_ref<'e>(error: &'e FalseError) -> __private::ErrorRef<'e> {
+        __private::ErrorRef::Error(error)
+    }
+}
+
+#[test]
+#[should_panic]
+fn expect_panic_bare() {
+    expect!(Err("expect error"));
+}
+
+#[test]
+#[should_panic]
+#[allow(clippy::unnecessary_literal_unwrap)]
+fn regular_panic_bare() {
+    let result: Result<(), &str> = Err("expect error");
+    result.unwrap();
+}
+
+#[test]
+#[should_panic]
+fn expect_panic_msg() {
+    expect!(Err("expect error"), "Some values: {}, {}", 1, 2);
+}
+
+#[test]
+#[should_panic]
+#[allow(clippy::unit_arg)]
+fn sanity_chain() {
+    fn foo(a: ()) -> Result<(), &'static str> {
+        Ok(a)
+    }
+
+    #[allow(unused_variables)]
+    fn bar(a: ()) -> Result<(), &'static str> {
+        Err("expect error")
+    }
+
+    let a = ();
+    expect!(bar(expect!(foo(a))));
+}
+
+#[test]
+#[should_panic]
+fn sanity_option_plain() {
+    expect!(None);
+}
+
+#[test]
+#[should_panic]
+fn sanity_option_msg() {
+    expect!(None, "Got None, expected 42");
+}
+
+#[test]
+fn expect_message_values() {
+    use std::panic::catch_unwind;
+
+    let message = "need a value";
+    let owned = format!("need {} values", 2);
+    let port = 80;
+    let cases = vec![
+        catch_unwind(|| expect!(None::<u32>, message)),
+        catch_unwind(|| expect!(None::<u32>, owned.clone())),
+        catch_unwind(|| expect!(None::<u32>, "need {port}")),
+        catch_unwind(|| expect_flat!(Some(None::<u32>), message)),
+        catch_unwind(|| expect_eq!(1, 2, &owned)),
+        catch_unwind(|| {
+            expect_let!(Some(_) = None::<u32>, message);
+            0
+        }),
+    ];
+    let messages: Vec<_> = cases
+        .into_iter()
+        .map(|case| caught(case.unwrap_err()).to_string())
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "need a value",
+            "need 2 values",
+            "need 80",
+            "need a value",
+            "need 2 values",
+            "need a value"
+        ]
+    );
+}
+
+#[test]
+fn failure_payload() {
+    use std::panic::catch_unwind;
+
+    let result: Result<u32, &str> = Err("expect error");
+    #[rustfmt::skip]
+    let (line, payload) = (line!(), catch_unwind(|| expect!(result, "Some values: {}, {}", 1, 2)));
+
+    let failure = caught(payload.unwrap_err());
+    assert_eq!(failure.file(), file!());
+    assert_eq!(failure.line(), line);
+    assert_eq!(failure.module_path(), module_path!());
+    assert_eq!(failure.expr(), "result");
+    assert_eq!(failure.error(), "\"expect error\"");
+    assert_eq!(failure.message(), Some("Some values: 1, 2"));
+    assert_eq!(failure.to_string(), "Some values: 1, 2");
+}
+
+#[test]
+fn failure_payload_bare() {
+    let payload = ::std::panic::catch_unwind(|| {
+        expect!(Err::<(), _>("expect error"));
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert_eq!(failure.message(), None);
+    assert_eq!(
+        failure.to_string(),
+        "`expect!(Err::<(), _>(\"expect error\"))` failed: \"expect error\""
+    );
+}
+
+#[test]
+fn failure_payload_no_debug() {
+    struct Handle;
+
+    let payload = ::std::panic::catch_unwind(|| {
+        let result: Result<(), Handle> = Err(Handle);
+        expect!(result, "no handle");
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert!(failure
+        .error()
+        .ends_with("Handle (error does not implement Debug)"));
+}
+
+#[test]
+fn expect_bare_no_debug() {
+    struct Handle;
+
+    assert!(expect!(Ok::<bool, Handle>(true)));
+
+    let payload = ::std::panic::catch_unwind(|| {
+        let result: Result<(), Handle> = Err(Handle);
+        expect!(result);
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert_eq!(
+        failure.to_string(),
+        format!(
+            "`expect!(result)` failed: {} (error does not implement Debug)",
+            ::std::any::type_name::<Handle>()
+        )
+    );
+}
+
+#[test]
+fn default_message_names_sub_expression() {
+    fn foo() -> Result<u32, &'static str> {
+        Ok(42)
+    }
+
+    fn bar(_: u32) -> Option<u32> {
+        None
+    }
+
+    let payload = ::std::panic::catch_unwind(|| {
+        expect!(bar(expect!(foo())));
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert_eq!(failure.expr(), "bar(expect!(foo()))");
+    assert_eq!(
+        failure.to_string(),
+        "`expect!(bar(expect!(foo())))` failed: expected Some(u32), got None"
+    );
+}
+
+#[test]
+fn expect_msg_binds_error() {
+    let path = "/etc/x.toml";
+    let payload = ::std::panic::catch_unwind(|| {
+        let result: Result<(), String> = Err("permission denied".to_string());
+        expect!(result, err => "failed to open {}: {}", path, err);
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert_eq!(
+        failure.message(),
+        Some("failed to open /etc/x.toml: permission denied")
+    );
+    assert_eq!(failure.error(), "\"permission denied\"");
+}
+
+#[test]
+fn expect_msg_binds_error_ok() {
+    let result: Result<u32, String> = Ok(42);
+    assert_eq!(expect!(result, err => "unreachable: {}", err), 42);
+}
+
+#[test]
+fn expect_err_returns_error() {
+    let result: Result<u32, &str> = Err("expect error");
+    assert_eq!(expect_err!(result), "expect error");
+    assert_eq!(expect_err!(result, "needed an error"), "expect error");
+}
+
+#[test]
+fn expect_err_panic() {
+    let payload = ::std::panic::catch_unwind(|| {
+        let result: Result<u32, &str> = Ok(42);
+        expect_err!(result);
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert_eq!(failure.macro_name(), "expect_err");
+    assert_eq!(
+        failure.to_string(),
+        "`expect_err!(result)` failed: expected Err, got Ok(42)"
+    );
+}
+
+#[test]
+#[should_panic]
+fn expect_err_panic_msg() {
+    expect_err!(Ok::<u32, ()>(42), "Some values: {}, {}", 1, 2);
+}
+
+#[test]
+fn expect_none_ok() {
+    expect_none!(None::<u32>);
+    expect_none!(None::<u32>, "Got Some, expected None");
+}
+
+#[test]
+fn expect_none_panic() {
+    let payload = ::std::panic::catch_unwind(|| {
+        expect_none!(Some("expect value"));
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert_eq!(
+        failure.to_string(),
+        "`expect_none!(Some(\"expect value\"))` failed: expected None, got Some(\"expect value\")"
+    );
+}
+
+#[test]
+fn none_error_location() {
+    let payload = ::std::panic::catch_unwind(|| {
+        expect!(None::<String>, err => "{:?} at line {}", err, err.location().unwrap().line());
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert_eq!(
+        failure.error(),
+        "expected Some(alloc::string::String), got None"
+    );
+    assert_eq!(
+        failure.message().unwrap(),
+        format!(
+            "expected Some(alloc::string::String), got None at line {}",
+            failure.line()
+        )
+    );
+}
+
+#[test]
+fn expect_ext_location() {
+    use std::panic::catch_unwind;
+
+    let result: Result<u32, &str> = Err("expect error");
+    let (line, payload) = (line!(), catch_unwind(|| result.expect_here()));
+
+    let failure = caught(payload.unwrap_err());
+    assert_eq!(failure.file(), file!());
+    assert_eq!(failure.line(), line);
+    assert_eq!(
+        failure.to_string(),
+        "`.expect_here()` failed: \"expect error\""
+    );
+}
+
+#[test]
+fn expect_ext_messages() {
+    assert_eq!(Some(42).expect_with(|_| unreachable!()), 42);
+
+    let payload = ::std::panic::catch_unwind(|| {
+        None::<u32>.expect_with(|e| format!("need a value: {}", e));
+    })
+    .unwrap_err();
+    let failure = caught(payload);
+    assert_eq!(failure.macro_name(), "expect_with");
+    assert_eq!(
+        failure.message(),
+        Some("need a value: expected Some(u32), got None")
+    );
+
+    let payload = ::std::panic::catch_unwind(|| {
+        None::<u32>.expect_msg(format_args!("Some values: {}, {}", 1, 2));
+    })
+    .unwrap_err();
+    let failure = caught(payload);
+    assert_eq!(failure.message(), Some("Some values: 1, 2"));
+}
+
+#[cfg(test)]
+thread_local!(static RECORDED: ::std::cell::RefCell<Vec<String>> = Default::default());
+
+#[cfg(test)]
+fn record_outer(failure: &ExpectFailure) -> ! {
+    RECORDED.with(|r| r.borrow_mut().push(format!("outer: {}", failure)));
+    ::std::panic::resume_unwind(Box::new(()))
+}
+
+#[cfg(test)]
+fn record_inner(failure: &ExpectFailure) -> ! {
+    RECORDED.with(|r| r.borrow_mut().push(format!("inner: {}", failure)));
+    ::std::panic::resume_unwind(Box::new(()))
+}
+
+#[test]
+fn with_handler_nests_and_restores() {
+    use std::panic::catch_unwind;
+
+    with_handler(record_outer, || {
+        assert!(catch_unwind(|| expect!(None::<u32>, "first")).is_err());
+        let inner = catch_unwind(|| with_handler(record_inner, || expect!(None::<u32>, "second")));
+        assert!(inner.is_err());
+        assert!(catch_unwind(|| expect!(None::<u32>, "third")).is_err());
+    });
+
+    let payload = catch_unwind(|| expect!(None::<u32>, "fourth")).unwrap_err();
+    assert_eq!(caught(payload).message(), Some("fourth"));
+    assert_eq!(
+        RECORDED.with(|r| r.borrow().clone()),
+        vec!["outer: first", "inner: second", "outer: third"]
+    );
+}
+
+#[test]
+fn context_frames() {
+    let path = "/etc/x.toml";
+    let payload = ::std::panic::catch_unwind(|| {
+        let _ctx = expect_context!("loading config {}", path);
+        {
+            let _ctx = expect_context!("parsing section [{}]", "db");
+        }
+        let _ctx = expect_context!("parsing section [{}]", "net");
+        expect!(None::<u16>, "missing port");
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert_eq!(
+        failure.context(),
+        ["loading config /etc/x.toml", "parsing section [net]"]
+    );
+    assert_eq!(
+        failure.to_string(),
+        "missing port\n  while loading config /etc/x.toml\n  while parsing section [net]"
+    );
+
+    // The frames were popped when the closure unwound.
+    let payload = ::std::panic::catch_unwind(|| expect!(None::<u16>)).unwrap_err();
+    assert!(caught(payload).context().is_empty());
+}
+
+#[test]
+fn context_is_lazy() {
+    struct Panics;
+
+    impl ::std::fmt::Display for Panics {
+        fn fmt(&self, _: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+            panic!("context formatted without a failure");
+        }
+    }
+
+    let _ctx = expect_context!("{}", Panics);
+    assert_eq!(expect!(Some(42)), 42);
+}
+
+#[test]
+fn context_guards_dropped_out_of_order() {
+    fn context() -> Vec<String> {
+        let payload = ::std::panic::catch_unwind(|| expect!(None::<u16>)).unwrap_err();
+        caught(payload).context().to_vec()
+    }
+
+    // As two futures holding guards across an `.await` might be.
+    let first = ContextGuard::push(|f: &mut fmt::Formatter| f.write_str("first"));
+    let second = ContextGuard::push(|f: &mut fmt::Formatter| f.write_str("second"));
+    drop(first);
+    assert_eq!(context(), ["second"]);
+    drop(second);
+    assert!(context().is_empty());
+}
+
+#[cfg(feature = "backtrace")]
+#[test]
+fn failure_backtrace() {
+    use std::backtrace::BacktraceStatus;
+
+    let payload = ::std::panic::catch_unwind(|| expect!(None::<u32>)).unwrap_err();
+    let failure = caught(payload);
+    assert_eq!(failure.backtrace().status(), BacktraceStatus::Captured);
+    assert!(!failure.to_string().contains("backtrace"));
+}
+
+/// The `ExpectFailure` of a panic caught with `catch_unwind`.
+#[cfg(test)]
+fn caught(payload: Box<dyn std::any::Any + Send>) -> ExpectFailure {
+    *payload.downcast::<ExpectFailure>().unwrap()
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct Chained(&'static str, Option<Box<Chained>>);
+
+#[cfg(test)]
+impl ::std::fmt::Display for Chained {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[cfg(test)]
+impl ::std::error::Error for Chained {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.1.as_ref().map(|e| &**e as _)
+    }
+}
+
+#[test]
+fn error_source_chain() {
+    let payload = ::std::panic::catch_unwind(|| {
+        let io = Chained("No such file or directory", None);
+        let config = Chained("failed to load config", Some(Box::new(io)));
+        let result: Result<(), Chained> = Err(Chained("failed to start", Some(Box::new(config))));
+        expect!(result);
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert_eq!(
+        failure.to_string(),
+        "`expect!(result)` failed: failed to start\n\
+         Caused by:\n    \
+         0: failed to load config\n    \
+         1: No such file or directory"
+    );
+}
+
+#[test]
+fn boxed_error_source_chain() {
+    use std::error::Error;
+
+    let payload = ::std::panic::catch_unwind(|| {
+        let io = Chained("No such file or directory", None);
+        let error = Chained("failed", Some(Box::new(io)));
+        let result: Result<(), Box<dyn Error>> = Err(Box::new(error));
+        expect!(result);
+    })
+    .unwrap_err();
+    let failure = caught(payload);
+    assert_eq!(
+        failure.error(),
+        "failed\nCaused by:\n    0: No such file or directory"
+    );
+
+    let payload = ::std::panic::catch_unwind(|| {
+        let io = Chained("No such file or directory", None);
+        let result: Result<(), Box<dyn Error + Send + Sync>> = Err(Box::new(io));
+        expect!(result; display);
+    })
+    .unwrap_err();
+    let failure = caught(payload);
+    assert_eq!(failure.error(), "No such file or directory");
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Big {
+    a: u32,
+    b: &'static str,
+}
+
+#[test]
+fn expect_format_selectors() {
+    use std::panic::catch_unwind;
+
+    let pretty = catch_unwind(|| expect!(Err::<(), _>(Big { a: 1, b: "x" }); pretty));
+    assert_eq!(
+        caught(pretty.unwrap_err()).error(),
+        "Big {\n    a: 1,\n    b: \"x\",\n}"
+    );
+
+    let debug = catch_unwind(|| expect!(Err::<(), _>(Chained("top", None)); debug));
+    assert_eq!(caught(debug.unwrap_err()).error(), "Chained(\"top\", None)");
+
+    let display = catch_unwind(|| expect!(Err::<(), _>("expect error"); display));
+    assert_eq!(caught(display.unwrap_err()).error(), "expect error");
+
+    // Like `Format::Display`, an error's source chain is kept.
+    let chained = Chained("top", Some(Box::new(Chained("root", None))));
+    let display = catch_unwind(|| expect!(Err::<(), _>(chained); display));
+    assert_eq!(
+        caught(display.unwrap_err()).error(),
+        "top\nCaused by:\n    0: root"
+    );
+}
+
+#[test]
+fn expect_bool() {
+    let () = expect!(1 + 1 == 2);
+
+    let payload = ::std::panic::catch_unwind(|| {
+        let path = ::std::path::Path::new("/does/not/exist");
+        expect!(path.exists());
+    })
+    .unwrap_err();
+
+    let failure = caught(payload);
+    assert_eq!(
+        failure.to_string(),
+        "`expect!(path.exists())` failed: condition was false"
+    );
+
+    let payload = ::std::panic::catch_unwind(|| expect!(1 > 2, err => "{:?}", err)).unwrap_err();
+    assert_eq!(caught(payload).to_string(), "condition was false: `1 > 2`");
+    let payload = ::std::panic::catch_unwind(|| expect!(1 > 2; debug)).unwrap_err();
+    assert_eq!(caught(payload).error(), "condition was false: `1 > 2`");
+}
+
+#[test]
+fn expect_flat_layers() {
+    use std::panic::catch_unwind;
+
+    assert_eq!(expect_flat!(Some(Ok::<u32, &str>(42))), 42);
+    assert_eq!(expect_flat!(Ok::<_, &str>(Some(42)), "unreachable"), 42);
+
+    let outer = catch_unwind(|| expect_flat!(None::<Result<u32, &str>>)).unwrap_err();
+    assert_eq!(
+        caught(outer).to_string(),
+        "`expect_flat!(None::<Result<u32, &str>>)` failed: outer layer failed: \
+         expected Some(core::result::Result<u32, &str>), got None"
+    );
+
+    let inner = catch_unwind(|| expect_flat!(Some(Err::<u32, _>("expect error")))).unwrap_err();
+    assert_eq!(
+        caught(inner).to_string(),
+        "`expect_flat!(Some(Err::<u32, _>(\"expect error\")))` failed: \
+         inner layer failed: \"expect error\""
+    );
+
+    let inner = catch_unwind(|| expect_flat!(Ok::<Option<u32>, &str>(None))).unwrap_err();
+    assert_eq!(
+        caught(inner).to_string(),
+        "`expect_flat!(Ok::<Option<u32>, &str>(None))` failed: \
+         inner layer failed: expected Some(u32), got None"
+    );
+}
+
+#[test]
+fn expect_thread_join() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::thread;
+
+    let handle = thread::Builder::new()
+        .name("worker-3".into())
+        .spawn(|| {
+            let _ctx = expect_context!("handling job {}", 7);
+            expect!(None::<u32>, "worker failed");
+        })
+        .unwrap();
+    let line = line!() - 3;
+    let joined = handle.join();
+
+    let payload = catch_unwind(AssertUnwindSafe(|| expect!(joined))).unwrap_err();
+    let failure = caught(payload);
+    assert_eq!(
+        failure.error(),
+        format!(
+            "thread 'worker-3' panicked: worker failed at {}:{}:13\n  while handling job 7",
+            file!(),
+            line
+        )
+    );
+
+    let joined = thread::spawn(|| panic!("plain {}", "panic")).join();
+    let payload = catch_unwind(AssertUnwindSafe(|| expect!(joined))).unwrap_err();
+    let failure = caught(payload);
+    assert_eq!(failure.error(), "thread panicked: plain panic");
+}
+
+#[test]
+fn expect_poll_and_control_flow() {
+    use std::ops::ControlFlow;
+    use std::panic::catch_unwind;
+    use std::task::Poll;
+
+    assert_eq!(expect!(Poll::Ready(42)), 42);
+    assert_eq!(expect_flat!(Poll::Ready(Ok::<u32, &str>(42))), 42);
+    assert_eq!(expect!(ControlFlow::Break::<u32, ()>(42)), 42);
+
+    let pending = catch_unwind(|| expect!(Poll::<u32>::Pending)).unwrap_err();
+    assert_eq!(
+        caught(pending).error(),
+        "expected Poll::Ready, got Poll::Pending"
+    );
+
+    let ready_err = catch_unwind(|| expect_flat!(Poll::Ready(Err::<u32, _>("expect error"))));
+    assert_eq!(
+        caught(ready_err.unwrap_err()).error(),
+        "inner layer failed: \"expect error\""
+    );
+
+    let proceed = catch_unwind(|| expect!(ControlFlow::Continue::<u32, _>(()))).unwrap_err();
+    assert_eq!(
+        caught(proceed).error(),
+        "expected ControlFlow::Break, got ControlFlow::Continue"
+    );
+}
+
+#[test]
+fn expect_let_binds() {
+    use std::panic::catch_unwind;
+
+    #[derive(Debug)]
+    enum Msg {
+        Data(Vec<u8>),
+        Ping,
+    }
+
+    expect_let!(Msg::Data(bytes) = Msg::Data(vec![4, 2]));
+    assert_eq!(bytes, [4, 2]);
+    expect_let!((Some(a), b) = (Some(1), 2), "unreachable");
+    assert_eq!(a + b, 3);
+
+    // Variables and fields are matched in place, so `ref` patterns leave them usable.
+    let msg = Msg::Data(vec![1]);
+    expect_let!(Msg::Data(ref data) = msg);
+    assert_eq!(data.len(), 1);
+    let pair = (data.len(), msg);
+    expect_let!(Msg::Data(ref data) = pair.1, "unreachable");
+    assert_eq!(data.len(), pair.0);
+
+    let ping = catch_unwind(|| {
+        let msg = Msg::Ping;
+        expect_let!(Msg::Data(ref _bytes) = msg);
+    })
+    .unwrap_err();
+    assert_eq!(
+        caught(ping).error(),
+        "expected Msg::Data(ref _bytes), got Ping"
+    );
+
+    let ping = catch_unwind(|| {
+        expect_let!(Msg::Data(_bytes) = Msg::Ping);
+    })
+    .unwrap_err();
+    assert_eq!(
+        caught(ping).to_string(),
+        "`expect_let!(Msg::Data(_bytes) = Msg::Ping)` failed: expected Msg::Data(_bytes), got Ping"
+    );
+
+    let message = catch_unwind(|| {
+        let msg = Msg::Ping;
+        expect_let!(Msg::Data(_bytes) = msg, "wanted data");
+    })
+    .unwrap_err();
+    let failure = caught(message);
+    assert_eq!(failure.to_string(), "wanted data");
+    assert_eq!(failure.expr(), "Msg::Data(_bytes) = msg");
+    assert_eq!(failure.error(), "Ping");
+}
+
+#[test]
+fn expect_matches_projects() {
+    use std::cell::Cell;
+    use std::net::SocketAddr;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+    let port = expect_matches!(addr, SocketAddr::V4(a) if a.port() != 0 => a.port());
+    assert_eq!(port, 8080);
+    assert_eq!(expect_matches!(Some(3), Some(n) => n * 2, "unreachable"), 6);
+
+    let projected = Cell::new(false);
+    let zero = catch_unwind(AssertUnwindSafe(|| {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        expect_matches!(addr, SocketAddr::V4(a) if a.port() != 0 => projected.set(true));
+    }))
+    .unwrap_err();
+    assert!(!projected.get());
+    let failure = caught(zero);
+    assert_eq!(failure.macro_name(), "expect_matches");
+    assert_eq!(
+        failure.expr(),
+        "addr, SocketAddr::V4(a) if a.port() != 0 => projected.set(true)"
+    );
+    assert_eq!(
+        failure.error(),
+        "expected SocketAddr::V4(a) if a.port() != 0, got 127.0.0.1:0"
+    );
+
+    let message = catch_unwind(|| expect_matches!(None::<u32>, Some(n) => n, "no {}", "n"));
+    let failure = caught(message.unwrap_err());
+    assert_eq!(failure.to_string(), "no n");
+    assert_eq!(failure.error(), "None");
+}
+
+#[test]
+fn expect_or_patterns() {
+    use std::panic::catch_unwind;
+
+    let result: Result<u32, u32> = Err(7);
+    assert_eq!(expect_matches!(result, Ok(n) | Err(n) if n > 0 => n), 7);
+    expect_let!((Some(n), _) | (_, Some(n)) = (None, Some(7)));
+    assert_eq!(n, 7);
+
+    let zero = catch_unwind(|| expect_matches!(Some(0), Some(1) | Some(2) => ())).unwrap_err();
+    let failure = caught(zero);
+    assert_eq!(failure.expr(), "Some(0), Some(1) | Some(2) => ()");
+    assert_eq!(failure.error(), "expected Some(1) | Some(2), got Some(0)");
+
+    let zero = catch_unwind(|| {
+        expect_let!(1 | 2 = 0, "not one or two");
+    })
+    .unwrap_err();
+    assert_eq!(caught(zero).expr(), "1 | 2 = 0");
+}
+
+#[test]
+fn expect_comparisons() {
+    use std::panic::catch_unwind;
+
+    let n: u32 = expect_eq!("42".parse().unwrap(), 42);
+    assert_eq!(n, 42);
+    assert_eq!(expect_ne!(String::from("a"), "b"), "a");
+    assert_eq!(expect_cmp!(n, <=, 42, "unreachable"), 42);
+
+    let eq = catch_unwind(|| expect_eq!(41 + 1, 43)).unwrap_err();
+    assert_eq!(
+        caught(eq).to_string(),
+        "`expect_eq!(41 + 1, 43)` failed: expected left == right\n  left: 42\n right: 43"
+    );
+
+    let ne = catch_unwind(|| expect_ne!(Some(1), Some(1), "duplicate")).unwrap_err();
+    let failure = caught(ne);
+    assert_eq!(failure.to_string(), "duplicate");
+    assert_eq!(failure.expr(), "Some(1), Some(1)");
+    assert_eq!(
+        failure.error(),
+        "expected left != right\n  left: Some(1)\n right: Some(1)"
+    );
+
+    let cmp = catch_unwind(|| expect_cmp!(3, <, 2)).unwrap_err();
+    assert_eq!(
+        caught(cmp).to_string(),
+        "`expect_cmp!(3, <, 2)` failed: expected left < right\n  left: 3\n right: 2"
+    );
+}
+
+#[test]
+fn expect_eq_diff() {
+    use std::panic::catch_unwind;
+
+    #[derive(Debug, PartialEq)]
+    struct Config {
+        host: &'static str,
+        port: u16,
+        workers: u8,
+    }
+
+    let left = Config {
+        host: "localhost",
+        port: 80,
+        workers: 4,
+    };
+    let right = Config {
+        host: "localhost",
+        port: 8080,
+        workers: 4,
+    };
+    let payload = catch_unwind(|| expect_eq!(left, right)).unwrap_err();
+    assert_eq!(
+        caught(payload).error(),
+        concat!(
+            "expected left == right\n",
+            "--- left\n",
+            "+++ right\n",
+            "@@ -1,5 +1,5 @@\n",
+            " Config {\n",
+            "     host: \"localhost\",\n",
+            "-    port: 80,\n",
+            "+    port: 8080,\n",
+            "     workers: 4,\n",
+            " }",
+        )
+    );
+}
+
+#[test]
+fn line_diff_hunks() {
+    let left = (1..20)
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let right = left
+        .replace("\n2\n", "\ntwo\n")
+        .replace("\n15\n", "\n15\n15.5\n");
+    assert_eq!(
+        LineDiff::new(&left, &right).to_string(),
+        "--- left\n+++ right\n\
+         @@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n\
+         @@ -13,6 +13,7 @@\n 13\n 14\n 15\n+15.5\n 16\n 17\n 18"
+    );
+    assert_eq!(
+        LineDiff::new("", "a").to_string(),
+        "--- left\n+++ right\n@@ -0,0 +1,1 @@\n+a"
+    );
+}
+
+#[test]
+fn expect_all_operands() {
+    use std::panic::catch_unwind;
+
+    let (a, b, c) = expect_all!(Some(1), Ok::<_, &str>("two"), true);
+    assert_eq!((a, b, c), (1, "two", ()));
+    assert_eq!(expect_all!(Some(1),), (1,));
+
+    let evaluated = ::std::cell::Cell::new(0);
+    let payload = catch_unwind(::std::panic::AssertUnwindSafe(|| {
+        expect_all!(
+            None::<u32>,
+            {
+                evaluated.set(1);
+                Some(2)
+            },
+            "forty-two".parse::<u32>(),
+        )
+    }))
+    .unwrap_err();
+    assert_eq!(evaluated.get(), 1);
+    let failure = caught(payload);
+    assert_eq!(failure.macro_name(), "expect_all");
+    assert_eq!(
+        failure.error(),
+        "2 operands failed\n  \
+         - `None::<u32>`: expected Some(u32), got None\n  \
+         - `\"forty-two\".parse::<u32>()`: invalid digit found in string"
+    );
+}
+
+#[test]
+fn expect_any_attempts() {
+    use std::cell::Cell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let evaluated = Cell::new(false);
+    let found = expect_any!(None, "42".parse::<u32>(), {
+        evaluated.set(true);
+        Some(7)
+    },);
+    assert_eq!(found, 42);
+    assert!(!evaluated.get());
+    assert_eq!(expect_any!(Some("only")), "only");
+
+    // An attempt that fails before one succeeds is never rendered.
+    struct Panics;
+
+    impl ::std::fmt::Debug for Panics {
+        fn fmt(&self, _: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+            panic!("attempt rendered although a later one succeeded");
+        }
+    }
+
+    assert_eq!(expect_any!(Err::<u32, _>(Panics), Ok::<_, Panics>(1)), 1);
+
+    let payload = catch_unwind(AssertUnwindSafe(|| {
+        expect_any!(
+            None::<u32>,
+            "forty-two".parse::<u32>(),
+            Err::<u32, _>("fallback")
+        )
+    }))
+    .unwrap_err();
+    let failure = caught(payload);
+    assert_eq!(failure.macro_name(), "expect_any");
+    assert_eq!(
+        failure.error(),
+        "every attempt failed\n  \
+         1. `None::<u32>`: expected Some(u32), got None\n  \
+         2. `\"forty-two\".parse::<u32>()`: invalid digit found in string\n  \
+         3. `Err::<u32, _>(\"fallback\")`: \"fallback\""
+    );
 }
@@ -0,0 +1,255 @@
+/* Copyright (c) 2018 Garrett Berg, vitiral@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+ * http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+ * http://opensource.org/licenses/MIT>, at your option. This file may not be
+ * copied, modified, or distributed except according to those terms.
+ */
+//! Pick how to render an error based on the traits it implements.
+//!
+//! This uses autoref specialization: the macros call `(&&&&Wrap(&err)).__expect_error_ref()` and
+//! method resolution picks the impl with the fewest auto-derefs, so the most capable impl wins.
+
+use std::any::{self, Any};
+use std::error::Error;
+use std::fmt;
+
+use diff::LineDiff;
+use failure::ExpectFailure;
+use format::{self, Format};
+
+/// A borrowed error, erased to whatever it can be formatted as.
+#[doc(hidden)]
+pub enum ErrorRef<'a> {
+    Payload(&'a (dyn Any + Send)),
+    Error(&'a (dyn Error + 'a)),
+    Debug(&'a (dyn fmt::Debug + 'a)),
+    Display(&'a (dyn fmt::Display + 'a)),
+    Opaque(&'static str),
+}
+
+impl<'a> ErrorRef<'a> {
+    /// Display the error using `format`.
+    pub(crate) fn render(&'a self, format: Format) -> Render<'a> {
+        Render(self, format)
+    }
+}
+
+impl<'a> fmt::Display for ErrorRef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.render(Format::Auto), f)
+    }
+}
+
+pub(crate) struct Render<'a>(&'a ErrorRef<'a>, Format);
+
+impl<'a> fmt::Display for Render<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.0, self.1) {
+            (&ErrorRef::Payload(payload), _) => {
+                if let Some(failure) = payload.downcast_ref::<ExpectFailure>() {
+                    write!(
+                        f,
+                        "thread '{}' panicked: {} at {}:{}:{}",
+                        failure.thread().unwrap_or("<unnamed>"),
+                        failure.summary(),
+                        failure.file(),
+                        failure.line(),
+                        failure.column()
+                    )?;
+                    failure.write_context(f)
+                } else if let Some(message) = payload.downcast_ref::<&'static str>() {
+                    write!(f, "thread panicked: {}", message)
+                } else if let Some(message) = payload.downcast_ref::<String>() {
+                    write!(f, "thread panicked: {}", message)
+                } else {
+                    f.write_str("thread panicked with a non-string payload")
+                }
+            }
+            (&ErrorRef::Error(e), Format::Auto) | (&ErrorRef::Error(e), Format::Display) => {
+                write!(f, "{}", e)?;
+                let mut source = e.source();
+                if source.is_some() {
+                    f.write_str("\nCaused by:")?;
+                }
+                let mut i = 0;
+                while let Some(cause) = source {
+                    write!(f, "\n    {}: {}", i, cause)?;
+                    source = cause.source();
+                    i += 1;
+                }
+                Ok(())
+            }
+            (&ErrorRef::Error(e), Format::Pretty) => write!(f, "{:#?}", e),
+            (&ErrorRef::Error(e), Format::Debug) => write!(f, "{:?}", e),
+            (&ErrorRef::Debug(e), Format::Pretty) => write!(f, "{:#?}", e),
+            (&ErrorRef::Debug(e), _) => write!(f, "{:?}", e),
+            (&ErrorRef::Display(e), _) => write!(f, "{}", e),
+            (&ErrorRef::Opaque(type_name), _) => {
+                write!(f, "{} (error does not implement Debug)", type_name)
+            }
+        }
+    }
+}
+
+/// A success value that was expected to be a failure.
+#[doc(hidden)]
+pub struct Unexpected<'a> {
+    pub expected: &'static str,
+    pub found: &'static str,
+    pub value: ErrorRef<'a>,
+}
+
+impl<'a> fmt::Debug for Unexpected<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format = if f.alternate() {
+            Format::Pretty
+        } else {
+            Format::Auto
+        };
+        write!(
+            f,
+            "expected {}, got {}({})",
+            self.expected,
+            self.found,
+            self.value.render(format)
+        )
+    }
+}
+
+/// A value that did not match the pattern of `expect_let!` or `expect_matches!`.
+#[doc(hidden)]
+pub struct Mismatch<'a> {
+    pub pattern: &'static str,
+    pub value: ErrorRef<'a>,
+}
+
+impl<'a> fmt::Debug for Mismatch<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format = if f.alternate() {
+            Format::Pretty
+        } else {
+            Format::Auto
+        };
+        write!(
+            f,
+            "expected {}, got {}",
+            self.pattern,
+            self.value.render(format)
+        )
+    }
+}
+
+/// The operands of a comparison macro whose comparison did not hold.
+#[doc(hidden)]
+pub struct Comparison<'a> {
+    pub op: &'static str,
+    pub left: &'a dyn fmt::Debug,
+    pub right: &'a dyn fmt::Debug,
+}
+
+impl<'a> fmt::Debug for Comparison<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.op == "==" && format::diff_enabled() {
+            let left = format!("{:#?}", self.left);
+            let right = format!("{:#?}", self.right);
+            if left.contains('\n') || right.contains('\n') {
+                return write!(
+                    f,
+                    "expected left == right\n{}",
+                    LineDiff::new(&left, &right)
+                );
+            }
+        }
+        if f.alternate() {
+            write!(
+                f,
+                "expected left {} right\n  left: {:#?}\n right: {:#?}",
+                self.op, self.left, self.right
+            )
+        } else {
+            write!(
+                f,
+                "expected left {} right\n  left: {:?}\n right: {:?}",
+                self.op, self.left, self.right
+            )
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct Wrap<'a, E: 'a>(pub &'a E);
+
+#[doc(hidden)]
+pub trait PayloadKind<'a> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a>;
+}
+
+impl<'a> PayloadKind<'a> for &&&Wrap<'a, Box<dyn Any + Send>> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a> {
+        ErrorRef::Payload(&**self.0)
+    }
+}
+
+/// `Box<dyn Error>` does not implement `Error` itself, so it is picked out before `ErrorKind`.
+#[doc(hidden)]
+pub trait BoxErrorKind<'a> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a>;
+}
+
+impl<'a> BoxErrorKind<'a> for &&&Wrap<'a, Box<dyn Error>> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a> {
+        ErrorRef::Error(&**self.0)
+    }
+}
+
+impl<'a> BoxErrorKind<'a> for &&&Wrap<'a, Box<dyn Error + Send + Sync>> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a> {
+        ErrorRef::Error(&**self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait ErrorKind<'a> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a>;
+}
+
+impl<'a, E: Error + 'a> ErrorKind<'a> for &&Wrap<'a, E> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a> {
+        ErrorRef::Error(self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait DebugKind<'a> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a>;
+}
+
+impl<'a, E: fmt::Debug + 'a> DebugKind<'a> for &Wrap<'a, E> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a> {
+        ErrorRef::Debug(self.0)
+    }
+}
+
+/// Takes the place of `DebugKind` for `expect!(result; display)`, which requires `Display`.
+#[doc(hidden)]
+pub trait DisplayKind<'a> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a>;
+}
+
+impl<'a, E: fmt::Display + 'a> DisplayKind<'a> for &Wrap<'a, E> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a> {
+        ErrorRef::Display(self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait OpaqueKind<'a> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a>;
+}
+
+impl<'a, E: 'a> OpaqueKind<'a> for Wrap<'a, E> {
+    fn __expect_error_ref(&self) -> ErrorRef<'a> {
+        ErrorRef::Opaque(any::type_name::<E>())
+    }
+}
@@ -0,0 +1,9 @@
+//! Helpers shared by the test binaries, each of which includes this as `mod common;`.
+
+use expect_macro::ExpectFailure;
+use std::any::Any;
+
+/// The `ExpectFailure` of a panic caught with `catch_unwind`, like the unit tests' helper.
+pub fn caught(payload: Box<dyn Any + Send>) -> ExpectFailure {
+    *payload.downcast::<ExpectFailure>().unwrap()
+}
@@ -0,0 +1,28 @@
+//! `expect_context!` expands into the caller's crate, which may forbid unsafe code.
+
+#![forbid(unsafe_code)]
+
+#[macro_use]
+extern crate expect_macro;
+
+mod common;
+
+use common::caught;
+use std::panic;
+use std::path::Path;
+
+fn load(path: &Path, name: String) -> usize {
+    let _ctx = expect_context!("loading config {}", path.display());
+    let _ctx = expect_context!("for {}", name);
+    // Neither argument was moved into its frame.
+    assert_eq!(name.len(), 3);
+    expect!(None::<usize>, "missing port")
+}
+
+#[test]
+fn context_borrows_its_arguments() {
+    let payload = panic::catch_unwind(|| load(Path::new("/etc/x.toml"), "web".to_string()));
+
+    let failure = caught(payload.unwrap_err());
+    assert_eq!(failure.context(), ["loading config /etc/x.toml", "for web"]);
+}
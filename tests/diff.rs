@@ -0,0 +1,33 @@
+//! `set_diff` and `EXPECT_MACRO_DIFF` apply to every thread of the process, so the tests that
+//! change them get a binary to themselves.
+
+#[macro_use]
+extern crate expect_macro;
+
+mod common;
+
+use common::caught;
+use std::env;
+use std::panic;
+
+#[derive(Debug, PartialEq)]
+struct Pair(u32, u32);
+
+#[test]
+fn process_wide_diff() {
+    env::set_var("EXPECT_MACRO_DIFF", "off");
+    assert!(!expect_macro::diff_enabled());
+    let payload = panic::catch_unwind(|| expect_eq!(Pair(1, 2), Pair(1, 3))).unwrap_err();
+    assert_eq!(
+        caught(payload).error(),
+        "expected left == right\n  left: Pair(1, 2)\n right: Pair(1, 3)"
+    );
+
+    // Setting it from code wins over the environment.
+    expect_macro::set_diff(true);
+    let payload = panic::catch_unwind(|| expect_eq!(Pair(1, 2), Pair(1, 3))).unwrap_err();
+    assert_eq!(
+        caught(payload).error(),
+        "expected left == right\n--- left\n+++ right\n@@ -1,4 +1,4 @@\n Pair(\n     1,\n-    2,\n+    3,\n )"
+    );
+}
@@ -0,0 +1,33 @@
+//! `set_format` changes how every later failure in the process is rendered, which would race with
+//! the unit tests, so it is exercised here instead.
+
+#[macro_use]
+extern crate expect_macro;
+
+mod common;
+
+use common::caught;
+use expect_macro::Format;
+use std::env;
+use std::panic;
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Big {
+    a: u32,
+}
+
+#[test]
+fn process_wide_format() {
+    env::set_var("EXPECT_MACRO_FORMAT", "pretty");
+    assert_eq!(expect_macro::format(), Format::Pretty);
+    let payload = panic::catch_unwind(|| expect!(Err::<(), _>(Big { a: 1 }))).unwrap_err();
+    assert_eq!(caught(payload).error(), "Big {\n    a: 1,\n}");
+
+    // Setting it from code wins over the environment, and per-call selectors win over both.
+    expect_macro::set_format(Format::Debug);
+    let payload = panic::catch_unwind(|| expect!(Err::<(), _>(Big { a: 1 }))).unwrap_err();
+    assert_eq!(caught(payload).error(), "Big { a: 1 }");
+    let payload = panic::catch_unwind(|| expect!(Err::<(), _>(Big { a: 1 }); pretty)).unwrap_err();
+    assert_eq!(caught(payload).error(), "Big {\n    a: 1,\n}");
+}
@@ -0,0 +1,37 @@
+//! A handler installed with `set_handler` can never be removed, so nothing else runs in this binary.
+
+#[macro_use]
+extern crate expect_macro;
+
+use expect_macro::ExpectFailure;
+use std::panic;
+
+#[derive(Debug)]
+struct Handled(String);
+
+fn handle(failure: &ExpectFailure) -> ! {
+    panic::resume_unwind(Box::new(Handled(failure.to_string())))
+}
+
+#[test]
+fn set_handler_routes_every_macro() {
+    expect_macro::set_handler(handle).unwrap();
+    assert!(expect_macro::set_handler(handle).is_err());
+
+    let payload = panic::catch_unwind(|| {
+        expect!(None::<u32>);
+    })
+    .unwrap_err();
+    let handled = payload.downcast_ref::<Handled>().unwrap();
+    assert_eq!(
+        handled.0,
+        "`expect!(None::<u32>)` failed: expected Some(u32), got None"
+    );
+
+    let payload = panic::catch_unwind(|| {
+        expect_err!(Ok::<u32, ()>(42), "Some values: {}, {}", 1, 2);
+    })
+    .unwrap_err();
+    let handled = payload.downcast_ref::<Handled>().unwrap();
+    assert_eq!(handled.0, "Some values: 1, 2");
+}
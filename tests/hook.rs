@@ -0,0 +1,80 @@
+//! The crate installs its panic hook on the first failure, and a process has only the one hook,
+//! so this binary owns it.
+
+#[macro_use]
+extern crate expect_macro;
+
+use std::env;
+use std::panic;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SEEN: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn first_failure_chains_the_hook() {
+    panic::set_hook(Box::new(|_| {
+        SEEN.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    // The failure is printed by the crate's hook, everything else still reaches the old one.
+    assert!(panic::catch_unwind(|| expect!(None::<u32>)).is_err());
+    assert_eq!(SEEN.load(Ordering::SeqCst), 0);
+    assert!(panic::catch_unwind(|| panic!("other")).is_err());
+    assert_eq!(SEEN.load(Ordering::SeqCst), 1);
+}
+
+/// Fails when run by `stderr_of_failure`, and passes otherwise.
+#[test]
+fn failing_child() {
+    if env::var_os("EXPECT_MACRO_FAIL").is_some() {
+        expect!(None::<u32>, "no port configured");
+    }
+}
+
+/// Run `failing_child` in a child process, where the panic reaches the real stderr.
+fn stderr_of_failure(vars: &[(&str, &str)]) -> String {
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["failing_child", "--exact", "--nocapture"])
+        .env_remove("RUST_BACKTRACE")
+        .env_remove("RUST_LIB_BACKTRACE")
+        .env("EXPECT_MACRO_FAIL", "1")
+        .envs(vars.iter().cloned())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn default_output() {
+    let stderr = stderr_of_failure(&[]);
+    let header = format!("thread 'failing_child' panicked at {}:", file!());
+    assert!(stderr.contains(&header), "{}", stderr);
+    assert!(stderr.contains(":\nno port configured\n"), "{}", stderr);
+    assert!(
+        stderr.contains("note: run with `RUST_BACKTRACE=1`"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("Box<dyn Any>"), "{}", stderr);
+}
+
+#[test]
+fn default_output_backtrace() {
+    let stderr = stderr_of_failure(&[("RUST_BACKTRACE", "1")]);
+    assert!(
+        stderr.contains("no port configured\nstack backtrace:\n"),
+        "{}",
+        stderr
+    );
+}
+
+#[cfg(feature = "backtrace")]
+#[test]
+fn default_output_lib_backtrace() {
+    let stderr = stderr_of_failure(&[("RUST_LIB_BACKTRACE", "1"), ("RUST_BACKTRACE", "0")]);
+    assert!(stderr.contains("stack backtrace:\n"), "{}", stderr);
+    let stderr = stderr_of_failure(&[("RUST_LIB_BACKTRACE", "0"), ("RUST_BACKTRACE", "1")]);
+    assert!(!stderr.contains("stack backtrace:\n"), "{}", stderr);
+}
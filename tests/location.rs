@@ -0,0 +1,24 @@
+//! What the panic hook sees of a failure. The hook is global, so this runs in a binary of its own.
+
+#[macro_use]
+extern crate expect_macro;
+
+use std::panic;
+use std::sync::Mutex;
+
+static LOCATION: Mutex<Option<(String, u32)>> = Mutex::new(None);
+
+#[test]
+fn panic_location_is_the_macro() {
+    // Install the crate's hook first so it is not installed over this one.
+    expect_macro::install_panic_hook();
+    panic::set_hook(Box::new(|info| {
+        let location = info.location().unwrap();
+        *LOCATION.lock().unwrap() = Some((location.file().to_string(), location.line()));
+    }));
+
+    let (line, payload) = (line!(), panic::catch_unwind(|| expect!(None::<u32>)));
+    assert!(payload.is_err());
+    let location = LOCATION.lock().unwrap().take();
+    assert_eq!(location, Some((file!().to_string(), line)));
+}